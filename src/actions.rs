@@ -0,0 +1,77 @@
+use anyhow::Result;
+use serde_json::json;
+use crate::config::BrowserConfig;
+use crate::{attach_tab, call_cdp, call_tab, clipboard, connect_cdp, windows, Tab};
+
+const ACTIONS: [&str; 10] = [
+    "Reload",
+    "Hard reload",
+    "Duplicate",
+    "Copy URL",
+    "Copy title",
+    "Copy Markdown link",
+    "Copy link as…",
+    "Mute",
+    "Unmute",
+    "Close",
+];
+
+/// Alt+1 on a tab: what else to do with it besides switching to it.
+pub async fn tab_menu(tab: &Tab, tabs: &[Tab], config: &BrowserConfig) -> Result<()> {
+    let destinations = windows::destinations(tab, tabs);
+    let moves: Vec<String> = destinations
+        .iter()
+        .map(|(label, _)| format!("Move to {}", label))
+        .collect();
+    let mut options: Vec<&str> = ACTIONS.to_vec();
+    options.extend(moves.iter().map(String::as_str));
+
+    match crate::rofi_choose(&tab.title, &options).as_str() {
+        "Reload" => reload(tab, false, config).await?,
+        "Hard reload" => reload(tab, true, config).await?,
+        "Duplicate" => duplicate(tab, config).await?,
+        "Copy URL" => clipboard::copy(&tab.url)?,
+        "Copy title" => clipboard::copy(&tab.title)?,
+        "Copy Markdown link" => clipboard::copy_link(&tab.title, &tab.url, clipboard::Format::Markdown)?,
+        "Copy link as…" => clipboard::choose_and_copy(&tab.title, &tab.url)?,
+        "Mute" => set_muted(tab, true, config).await?,
+        "Unmute" => set_muted(tab, false, config).await?,
+        "Close" => crate::close_tab(&tab.target_id, config).await?,
+        choice => {
+            if let Some(i) = moves.iter().position(|m| m == choice) {
+                windows::move_tab(tab, destinations[i].1, tabs, config).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `Page.reload`; a hard reload bypasses the cache like Ctrl+Shift+R.
+pub async fn reload(tab: &Tab, ignore_cache: bool, config: &BrowserConfig) -> Result<()> {
+    let mut ws = connect_cdp(config).await?;
+    let session = attach_tab(&mut ws, 1, &tab.target_id).await?;
+    call_tab(&mut ws, 2, &session, "Page.reload", json!({ "ignoreCache": ignore_cache })).await?;
+    Ok(())
+}
+
+/// Opens the tab's URL again next to it; new tabs land in the last focused
+/// window, so the original is activated first.
+pub async fn duplicate(tab: &Tab, config: &BrowserConfig) -> Result<()> {
+    let mut ws = connect_cdp(config).await?;
+    call_cdp(&mut ws, 1, "Target.activateTarget", json!({ "targetId": tab.target_id })).await?;
+    call_cdp(&mut ws, 2, "Target.createTarget", json!({ "url": tab.url })).await?;
+    Ok(())
+}
+
+/// CDP has no tab-level mute, so this (un)mutes the page's media elements;
+/// ones the page creates afterwards aren't affected.
+pub async fn set_muted(tab: &Tab, muted: bool, config: &BrowserConfig) -> Result<()> {
+    let mut ws = connect_cdp(config).await?;
+    let session = attach_tab(&mut ws, 1, &tab.target_id).await?;
+    let expression = format!(
+        "document.querySelectorAll('audio, video').forEach(m => m.muted = {})",
+        muted
+    );
+    call_tab(&mut ws, 2, &session, "Runtime.evaluate", json!({ "expression": expression })).await?;
+    Ok(())
+}
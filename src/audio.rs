@@ -0,0 +1,114 @@
+use anyhow::Result;
+use serde_json::json;
+use std::time::Duration;
+use crate::config::BrowserConfig;
+use tokio::time::Instant;
+use crate::{call_cdp_batch_until, call_tab_batch_until, connect_cdp, CdpSocket, Tab};
+
+/// Tabs that don't answer in time (frozen or still loading) show no icon;
+/// the others still do.
+const TIMEOUT: Duration = Duration::from_millis(800);
+
+/// 0 when nothing plays, 1 when something is audible, 2 when all playing
+/// media is muted.
+const PLAYING: &str = "(() => {
+    const playing = [...document.querySelectorAll('audio, video')]
+        .filter(m => !m.paused && !m.ended && m.readyState > 2);
+    if (playing.length == 0) return 0;
+    return playing.some(m => !m.muted && m.volume > 0) ? 1 : 2;
+})()";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Audio {
+    Playing,
+    Muted,
+}
+
+impl Audio {
+    /// Prefix for the tab's title in the main menu.
+    pub fn icon(self) -> &'static str {
+        match self {
+            Audio::Playing => "🔊 ",
+            Audio::Muted => "🔇 ",
+        }
+    }
+}
+
+/// Sets `tab.audio` from each page's `<audio>` and `<video>` elements. CDP
+/// doesn't expose Chromium's own audible flag, so sound made through Web
+/// Audio alone isn't noticed.
+///
+/// Only the main menu and `bbr tabs suspend` call this, since it touches
+/// every tab.
+pub async fn assign(tabs: &mut [Tab], config: &BrowserConfig) {
+    let Ok(mut ws) = connect_cdp(config).await else { return };
+    if let Ok(states) = query(&mut ws, tabs).await {
+        for (tab, audio) in tabs.iter_mut().zip(states) {
+            tab.audio = audio;
+        }
+    }
+}
+
+async fn query(ws: &mut CdpSocket, tabs: &[Tab]) -> Result<Vec<Option<Audio>>> {
+    let deadline = Instant::now() + TIMEOUT;
+    let attach = tabs
+        .iter()
+        .map(|t| ("Target.attachToTarget", json!({ "targetId": t.target_id, "flatten": true })))
+        .collect();
+    let sessions = call_cdp_batch_until(ws, 200, attach, deadline).await?;
+
+    let params = json!({
+        "expression": PLAYING,
+        "returnByValue": true,
+        "timeout": TIMEOUT.as_millis() as u64,
+    });
+    let evaluate = sessions
+        .iter()
+        .filter_map(|s| s["sessionId"].as_str())
+        .map(|session| (session, "Runtime.evaluate", params.clone()))
+        .collect();
+    // Ids past every attach, so a late attach reply can't pass for a result.
+    let first_id = 200 + tabs.len() as u64;
+    let mut results = call_tab_batch_until(ws, first_id, evaluate, deadline).await?.into_iter();
+
+    Ok(sessions
+        .iter()
+        .map(|s| {
+            s["sessionId"].as_str()?;
+            match results.next()?["result"]["value"].as_i64() {
+                Some(1) => Some(Audio::Playing),
+                Some(2) => Some(Audio::Muted),
+                _ => None,
+            }
+        })
+        .collect())
+}
+
+/// Switches to the tab making sound, asking which one when several are.
+pub async fn go_to_playing(tabs: &[Tab], config: &BrowserConfig) -> Result<()> {
+    let playing: Vec<&Tab> = tabs.iter().filter(|t| t.audio.is_some()).collect();
+    let tab = match playing.as_slice() {
+        [] => return Ok(()),
+        [tab] => *tab,
+        _ => {
+            let lines: Vec<String> = playing
+                .iter()
+                .map(|t| format!("{}{} - {}", t.audio.map(Audio::icon).unwrap_or_default(), t.title, t.url))
+                .collect();
+            let choice = crate::rofi_choose("Playing", &lines.iter().map(String::as_str).collect::<Vec<_>>());
+            match lines.iter().position(|l| *l == choice) {
+                Some(i) => playing[i],
+                None => return Ok(()),
+            }
+        }
+    };
+    crate::focus::tab(&tab.target_id, &tab.title, config).await
+}
+
+/// Mutes the media of every tab that is playing audibly.
+pub async fn mute_all(tabs: &[Tab], config: &BrowserConfig) -> Result<()> {
+    for tab in tabs.iter().filter(|t| t.audio == Some(Audio::Playing)) {
+        crate::actions::set_muted(tab, true, config).await?;
+    }
+    Ok(())
+}
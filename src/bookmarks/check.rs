@@ -0,0 +1,271 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+use url::Url;
+use super::Bookmark;
+
+/// How many `curl`s run at once.
+const PARALLEL: usize = 16;
+
+#[derive(Debug, Clone)]
+pub enum Subject {
+    Bookmark(Bookmark),
+    /// A surfraw line that didn't parse as `name url`.
+    Line(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// Same URL as the named bookmark, which is kept.
+    Duplicate(String),
+    Malformed(String),
+    Dead(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub subject: Subject,
+    pub problem: Problem,
+}
+
+impl Finding {
+    pub fn label(&self) -> String {
+        let subject = match &self.subject {
+            Subject::Bookmark(bookmark) => bookmark.label(),
+            Subject::Line(line) => format!("\"{}\"", line),
+        };
+        match &self.problem {
+            Problem::Duplicate(of) => format!("duplicate  {}  (same as {})", subject, of),
+            Problem::Malformed(why) => format!("malformed  {}  ({})", subject, why),
+            Problem::Dead(why) => format!("dead       {}  ({})", subject, why),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Options {
+    /// Skip the network checks.
+    pub offline: bool,
+    pub timeout: Duration,
+}
+
+/// Problems with `bookmarks`, in order, and with the unparsable surfraw lines
+/// in `malformed`. When URLs repeat, the first bookmark is the one kept.
+pub fn check(bookmarks: &[Bookmark], malformed: Vec<String>, options: &Options) -> Result<Vec<Finding>> {
+    let mut findings: Vec<Finding> = malformed
+        .into_iter()
+        .map(|line| Finding {
+            subject: Subject::Line(line),
+            problem: Problem::Malformed("no URL".to_string()),
+        })
+        .collect();
+
+    // The first bookmark with a URL is the original; later ones are duplicates.
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    let mut live = Vec::new();
+    for bookmark in bookmarks {
+        let url = match Url::parse(&bookmark.url) {
+            Ok(url) => url,
+            Err(e) => {
+                findings.push(Finding {
+                    subject: Subject::Bookmark(bookmark.clone()),
+                    problem: Problem::Malformed(e.to_string()),
+                });
+                continue;
+            }
+        };
+        if let Some(first) = seen.get(url.as_str()) {
+            findings.push(Finding {
+                subject: Subject::Bookmark(bookmark.clone()),
+                problem: Problem::Duplicate(first.to_string()),
+            });
+            continue;
+        }
+        seen.insert(url.to_string(), &bookmark.name);
+        if matches!(url.scheme(), "http" | "https") {
+            live.push(bookmark);
+        }
+    }
+
+    if !options.offline {
+        for batch in live.chunks(PARALLEL) {
+            let probes = batch
+                .iter()
+                .map(|bookmark| probe(&bookmark.url, options.timeout))
+                .collect::<Result<Vec<_>>>()?;
+            for (bookmark, probe) in batch.iter().zip(probes) {
+                let output = probe.wait_with_output()?;
+                if let Some(why) = verdict(output.status.code(), &output.stdout) {
+                    findings.push(Finding {
+                        subject: Subject::Bookmark((*bookmark).clone()),
+                        problem: Problem::Dead(why),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Starts a `curl` that prints the final status code after redirects.
+fn probe(url: &str, timeout: Duration) -> Result<Child> {
+    Ok(Command::new("curl")
+        .args(["-s", "-L", "-o", "/dev/null", "-w", "%{http_code}"])
+        .args(["--max-time", &timeout.as_secs_f64().to_string()])
+        .args(["-A", "Mozilla/5.0"])
+        .arg("--")
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?)
+}
+
+/// Why a probe that exited with `code` and printed `stdout` counts as dead,
+/// if it does.
+fn verdict(code: Option<i32>, stdout: &[u8]) -> Option<String> {
+    let why = match code {
+        Some(0) => {
+            let status: u16 = String::from_utf8_lossy(stdout).trim().parse().unwrap_or(0);
+            if status < 400 {
+                return None;
+            }
+            format!("HTTP {}", status)
+        }
+        Some(6) => "host not found".to_string(),
+        Some(7) => "connection refused".to_string(),
+        Some(28) => "timed out".to_string(),
+        Some(35) | Some(60) => "TLS error".to_string(),
+        Some(code) => format!("curl error {}", code),
+        None => "curl was killed".to_string(),
+    };
+    Some(why)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmarks::Source;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serves `/ok` with 200, `/gone` with 404 and never answers `/hang`.
+    fn serve() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                thread::spawn(move || {
+                    let mut line = String::new();
+                    BufReader::new(&stream).read_line(&mut line).unwrap();
+                    let status = match line.split(' ').nth(1) {
+                        Some("/ok") => "200 OK",
+                        Some("/gone") => "404 Not Found",
+                        _ => {
+                            thread::sleep(Duration::from_secs(5));
+                            return;
+                        }
+                    };
+                    let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                });
+            }
+        });
+        port
+    }
+
+    /// A local port nothing listens on.
+    fn closed_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    fn bookmark(name: &str, url: &str) -> Bookmark {
+        Bookmark {
+            name: name.to_string(),
+            url: url.to_string(),
+            folder: String::new(),
+            tags: Vec::new(),
+            source: Source::Surfraw,
+        }
+    }
+
+    fn summary(findings: &[Finding]) -> Vec<(String, Problem)> {
+        findings
+            .iter()
+            .map(|finding| match &finding.subject {
+                Subject::Bookmark(bookmark) => (bookmark.name.clone(), finding.problem.clone()),
+                Subject::Line(line) => (line.clone(), finding.problem.clone()),
+            })
+            .collect()
+    }
+
+    fn options(offline: bool) -> Options {
+        Options { offline, timeout: Duration::from_millis(700) }
+    }
+
+    #[test]
+    fn finds_dead_duplicate_and_malformed_bookmarks() {
+        let port = serve();
+        let base = format!("http://127.0.0.1:{}", port);
+        let bookmarks = [
+            bookmark("ok", &format!("{}/ok", base)),
+            bookmark("gone", &format!("{}/gone", base)),
+            bookmark("hang", &format!("{}/hang", base)),
+            bookmark("refused", &format!("http://127.0.0.1:{}/", closed_port())),
+            bookmark("again", &format!("{}/ok", base)),
+            bookmark("broken", "not a url"),
+            bookmark("local", "file:///nonexistent"),
+        ];
+
+        let findings = check(&bookmarks, vec!["justaname".to_string()], &options(false)).unwrap();
+        assert_eq!(
+            summary(&findings),
+            [
+                ("justaname".to_string(), Problem::Malformed("no URL".to_string())),
+                ("again".to_string(), Problem::Duplicate("ok".to_string())),
+                ("broken".to_string(), Problem::Malformed("relative URL without a base".to_string())),
+                ("gone".to_string(), Problem::Dead("HTTP 404".to_string())),
+                ("hang".to_string(), Problem::Dead("timed out".to_string())),
+                ("refused".to_string(), Problem::Dead("connection refused".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn offline_skips_the_network() {
+        let bookmarks = [
+            bookmark("refused", &format!("http://127.0.0.1:{}/", closed_port())),
+            bookmark("again", &format!("http://127.0.0.1:{}/", closed_port())),
+        ];
+        let findings = check(&bookmarks, Vec::new(), &options(true)).unwrap();
+        assert!(findings.iter().all(|finding| !matches!(finding.problem, Problem::Dead(_))));
+    }
+
+    #[test]
+    fn duplicates_compare_normalised_urls() {
+        let bookmarks = [
+            bookmark("first", "https://Example.com"),
+            bookmark("second", "https://example.com/"),
+        ];
+        let findings = check(&bookmarks, Vec::new(), &options(true)).unwrap();
+        assert_eq!(summary(&findings), [("second".to_string(), Problem::Duplicate("first".to_string()))]);
+    }
+
+    #[test]
+    fn maps_curl_results_to_verdicts() {
+        assert_eq!(verdict(Some(0), b"200"), None);
+        assert_eq!(verdict(Some(0), b"301\n"), None);
+        assert_eq!(verdict(Some(0), b"404"), Some("HTTP 404".to_string()));
+        assert_eq!(verdict(Some(0), b"503"), Some("HTTP 503".to_string()));
+        assert_eq!(verdict(Some(6), b""), Some("host not found".to_string()));
+        assert_eq!(verdict(Some(7), b"000"), Some("connection refused".to_string()));
+        assert_eq!(verdict(Some(28), b"000"), Some("timed out".to_string()));
+        assert_eq!(verdict(Some(35), b""), Some("TLS error".to_string()));
+        assert_eq!(verdict(Some(60), b""), Some("TLS error".to_string()));
+        assert_eq!(verdict(Some(52), b""), Some("curl error 52".to_string()));
+        assert_eq!(verdict(None, b""), Some("curl was killed".to_string()));
+    }
+}
@@ -0,0 +1,485 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use super::{Bookmark, Source};
+use crate::config::BrowserConfig;
+
+/// Top-level folders in the order Chromium feeds them into the checksum.
+const ROOTS: [&str; 3] = ["bookmark_bar", "other", "synced"];
+
+/// The profile's `Bookmarks` JSON file.
+///
+/// The browser keeps its own copy in memory and rewrites the file on every
+/// change, so edits are only safe while it is closed (see [`browser_running`]).
+pub struct ChromiumBookmarks {
+    path: PathBuf,
+    root: Value,
+}
+
+impl ChromiumBookmarks {
+    pub fn load(config: &BrowserConfig) -> Result<Self> {
+        let path = profile_dir(config).join("Bookmarks");
+        let root = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        Ok(Self { path, root })
+    }
+
+    /// An empty tree with Chromium's three permanent folders, for exports.
+    pub fn empty() -> Self {
+        let root = json!({
+            "checksum": "",
+            "roots": {
+                "bookmark_bar": permanent("1", "0bc5d13f-2cba-5d74-951f-3f233fe6c908", "Bookmarks bar"),
+                "other": permanent("2", "82b081ec-3dd3-529c-8475-ab6c344590dd", "Other bookmarks"),
+                "synced": permanent("3", "4cf2e351-0e85-532b-bb37-df045d8f8d0f", "Mobile bookmarks"),
+            },
+            "version": 1
+        });
+        Self { path: PathBuf::new(), root }
+    }
+
+    /// Parses a `Bookmarks` file that isn't the profile's own, e.g. for import.
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(Self { path: PathBuf::new(), root: serde_json::from_str(text)? })
+    }
+
+    pub fn bookmarks(&self) -> Vec<Bookmark> {
+        let mut out = Vec::new();
+        for key in ROOTS {
+            let node = &self.root["roots"][key];
+            if node.is_object() {
+                collect(node, "", &mut out);
+            }
+        }
+        out
+    }
+
+    /// Every folder path, e.g. `Bookmarks bar/Dev`.
+    pub fn folders(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for key in ROOTS {
+            let node = &self.root["roots"][key];
+            if node.is_object() {
+                collect_folders(node, "", &mut out);
+            }
+        }
+        out
+    }
+
+    pub fn rename(&mut self, id: &str, name: &str) -> Result<()> {
+        let node = self.find_mut(id)?;
+        node["name"] = json!(name);
+        Ok(())
+    }
+
+    pub fn set_url(&mut self, id: &str, url: &str) -> Result<()> {
+        let node = self.find_mut(id)?;
+        node["url"] = json!(url);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<Value> {
+        let (parent, index) = self
+            .locate(id)
+            .ok_or_else(|| anyhow!("No bookmark with id {}", id))?;
+        let children = self
+            .node_at_mut(&parent)
+            .and_then(|n| n["children"].as_array_mut())
+            .ok_or_else(|| anyhow!("Bookmark {} has no parent folder", id))?;
+        Ok(children.remove(index))
+    }
+
+    pub fn move_to(&mut self, id: &str, folder: &str) -> Result<()> {
+        if self.folder_path(folder).is_none() {
+            return Err(anyhow!("No bookmark folder '{}'", folder));
+        }
+        let node = self.remove(id)?;
+        // Look the folder up again: removing the node may have shifted it.
+        let target = self
+            .folder_path(folder)
+            .ok_or_else(|| anyhow!("No bookmark folder '{}'", folder))?;
+        let folder = self
+            .node_at_mut(&target)
+            .ok_or_else(|| anyhow!("No bookmark folder '{}'", folder))?;
+        folder["children"]
+            .as_array_mut()
+            .ok_or_else(|| anyhow!("Bookmark folder has no children"))?
+            .push(node);
+        Ok(())
+    }
+
+    /// Appends `bookmarks`, creating their folders as needed. A folder path
+    /// starting with a permanent folder's name goes there, anything else under
+    /// "Other bookmarks". Tags are kept in each node's `meta_info`.
+    pub fn add_all(&mut self, bookmarks: &[Bookmark]) -> Result<()> {
+        let mut next_id = max_id(&self.root["roots"]) + 1;
+        let now = crate::chrome_time::now().to_string();
+
+        for bookmark in bookmarks {
+            let folder = self.ensure_folder(&bookmark.folder, &mut next_id, &now)?;
+            let mut node = json!({
+                "date_added": now,
+                "date_last_used": "0",
+                "guid": new_guid()?,
+                "id": next_id.to_string(),
+                "name": bookmark.name,
+                "type": "url",
+                "url": bookmark.url,
+            });
+            if !bookmark.tags.is_empty() {
+                node["meta_info"] = json!({ "tags": bookmark.tags.join(",") });
+            }
+            next_id += 1;
+
+            self.node_at_mut(&folder)
+                .and_then(|f| f["children"].as_array_mut())
+                .ok_or_else(|| anyhow!("Bookmark folder has no children"))?
+                .push(node);
+        }
+        Ok(())
+    }
+
+    pub fn save(&mut self) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, self.serialize()?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// The file's JSON text, with the checksum brought up to date.
+    pub fn serialize(&mut self) -> Result<String> {
+        self.root["checksum"] = json!(checksum(&self.root));
+        Ok(serde_json::to_string_pretty(&self.root)?)
+    }
+
+    fn ensure_folder(&mut self, folder: &str, next_id: &mut u64, now: &str) -> Result<Vec<Step>> {
+        let mut segments: Vec<&str> = folder.split('/').filter(|s| !s.is_empty()).collect();
+        let key = match ROOTS
+            .iter()
+            .find(|key| segments.first().is_some_and(|s| self.root["roots"][**key]["name"] == *s))
+        {
+            Some(key) => {
+                segments.remove(0);
+                *key
+            }
+            None => "other",
+        };
+
+        let mut path = vec![Step::Root(key)];
+        for segment in segments {
+            let children = self
+                .node_at_mut(&path)
+                .and_then(|n| n["children"].as_array_mut())
+                .ok_or_else(|| anyhow!("Bookmark folder has no children"))?;
+            let index = match children
+                .iter()
+                .position(|c| c["type"] == "folder" && c["name"] == segment)
+            {
+                Some(index) => index,
+                None => {
+                    children.push(json!({
+                        "children": [],
+                        "date_added": now,
+                        "date_last_used": "0",
+                        "date_modified": now,
+                        "guid": new_guid()?,
+                        "id": next_id.to_string(),
+                        "name": segment,
+                        "type": "folder",
+                    }));
+                    *next_id += 1;
+                    children.len() - 1
+                }
+            };
+            path.push(Step::Child(index));
+        }
+        Ok(path)
+    }
+
+    fn find_mut(&mut self, id: &str) -> Result<&mut Value> {
+        let (mut path, index) = self
+            .locate(id)
+            .ok_or_else(|| anyhow!("No bookmark with id {}", id))?;
+        path.push(Step::Child(index));
+        self.node_at_mut(&path)
+            .ok_or_else(|| anyhow!("No bookmark with id {}", id))
+    }
+
+    /// Path to the parent folder of `id` and its index within it.
+    fn locate(&self, id: &str) -> Option<(Vec<Step>, usize)> {
+        fn walk(node: &Value, id: &str, path: &mut Vec<Step>) -> Option<usize> {
+            let children = node["children"].as_array()?;
+            for (i, child) in children.iter().enumerate() {
+                if child["id"] == id {
+                    return Some(i);
+                }
+                path.push(Step::Child(i));
+                if let Some(found) = walk(child, id, path) {
+                    return Some(found);
+                }
+                path.pop();
+            }
+            None
+        }
+
+        for key in ROOTS {
+            let mut path = vec![Step::Root(key)];
+            if let Some(index) = walk(&self.root["roots"][key], id, &mut path) {
+                return Some((path, index));
+            }
+        }
+        None
+    }
+
+    fn folder_path(&self, folder: &str) -> Option<Vec<Step>> {
+        fn walk(node: &Value, prefix: &str, folder: &str, path: &mut Vec<Step>) -> bool {
+            if prefix == folder {
+                return true;
+            }
+            let Some(children) = node["children"].as_array() else { return false };
+            for (i, child) in children.iter().enumerate() {
+                if child["type"] != "folder" {
+                    continue;
+                }
+                path.push(Step::Child(i));
+                let name = format!("{}/{}", prefix, child["name"].as_str().unwrap_or_default());
+                if walk(child, &name, folder, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+
+        for key in ROOTS {
+            let node = &self.root["roots"][key];
+            let mut path = vec![Step::Root(key)];
+            if walk(node, node["name"].as_str().unwrap_or(key), folder, &mut path) {
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    fn node_at_mut(&mut self, path: &[Step]) -> Option<&mut Value> {
+        let mut node = &mut self.root;
+        for step in path {
+            node = match step {
+                Step::Root(key) => node.get_mut("roots")?.get_mut(*key)?,
+                Step::Child(i) => node.get_mut("children")?.get_mut(*i)?,
+            };
+        }
+        Some(node)
+    }
+}
+
+enum Step {
+    Root(&'static str),
+    Child(usize),
+}
+
+/// The browser holds a `SingletonLock` in its user data dir while running.
+pub fn browser_running(config: &BrowserConfig) -> bool {
+    config.data_dir().join("SingletonLock").symlink_metadata().is_ok()
+}
+
+fn profile_dir(config: &BrowserConfig) -> PathBuf {
+    Path::new(&config.history_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+fn collect(node: &Value, prefix: &str, out: &mut Vec<Bookmark>) {
+    let name = node["name"].as_str().unwrap_or_default();
+    let folder = if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    };
+
+    for child in node["children"].as_array().into_iter().flatten() {
+        match child["type"].as_str() {
+            Some("url") => out.push(Bookmark {
+                name: child["name"].as_str().unwrap_or_default().to_string(),
+                url: child["url"].as_str().unwrap_or_default().to_string(),
+                folder: folder.clone(),
+                tags: child["meta_info"]["tags"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                source: Source::Chromium {
+                    id: child["id"].as_str().unwrap_or_default().to_string(),
+                },
+            }),
+            Some("folder") => collect(child, &folder, out),
+            _ => {}
+        }
+    }
+}
+
+fn collect_folders(node: &Value, prefix: &str, out: &mut Vec<String>) {
+    let name = node["name"].as_str().unwrap_or_default();
+    let folder = if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    };
+    out.push(folder.clone());
+
+    for child in node["children"].as_array().into_iter().flatten() {
+        if child["type"] == "folder" {
+            collect_folders(child, &folder, out);
+        }
+    }
+}
+
+fn permanent(id: &str, guid: &str, name: &str) -> Value {
+    json!({
+        "children": [],
+        "date_added": "0",
+        "date_modified": "0",
+        "guid": guid,
+        "id": id,
+        "name": name,
+        "type": "folder",
+    })
+}
+
+fn max_id(node: &Value) -> u64 {
+    let own = node["id"].as_str().and_then(|id| id.parse().ok()).unwrap_or(0);
+    let children = match node {
+        Value::Object(map) if node.get("type").is_none() => map.values().map(max_id).max(),
+        _ => node["children"].as_array().and_then(|c| c.iter().map(max_id).max()),
+    };
+    own.max(children.unwrap_or(0))
+}
+
+/// A random RFC 4122 version 4 UUID, as Chromium expects in `guid`.
+fn new_guid() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32]
+    ))
+}
+
+/// Reproduces Chromium's `BookmarkCodec` checksum: MD5 over each node's id,
+/// UTF-16LE title and type (plus URL), depth-first in root order.
+fn checksum(root: &Value) -> String {
+    fn update(ctx: &mut md5::Context, node: &Value) {
+        let utf16: Vec<u8> = node["name"]
+            .as_str()
+            .unwrap_or_default()
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+
+        ctx.consume(node["id"].as_str().unwrap_or_default());
+        ctx.consume(&utf16);
+        if node["type"] == "url" {
+            ctx.consume("url");
+            ctx.consume(node["url"].as_str().unwrap_or_default());
+        } else {
+            ctx.consume("folder");
+            for child in node["children"].as_array().into_iter().flatten() {
+                update(ctx, child);
+            }
+        }
+    }
+
+    let mut ctx = md5::Context::new();
+    for key in ROOTS {
+        let node = &root["roots"][key];
+        if node.is_object() {
+            update(&mut ctx, node);
+        }
+    }
+    format!("{:x}", ctx.compute())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `Bookmarks` file as Chromium writes it, with a folder, a nested URL
+    /// and a non-ASCII title; the checksum was worked out separately from
+    /// `BookmarkCodec`'s rules.
+    const BOOKMARKS: &str = r#"{
+   "checksum": "",
+   "roots": {
+      "bookmark_bar": {
+         "children": [ {
+            "date_added": "13350000000000000",
+            "guid": "0d5c2a3e-5f5b-4c8e-9a53-1b6f3c1d2e01",
+            "id": "4",
+            "name": "Rust – docs",
+            "type": "url",
+            "url": "https://doc.rust-lang.org/"
+         }, {
+            "children": [ {
+               "date_added": "13350000000000000",
+               "guid": "0d5c2a3e-5f5b-4c8e-9a53-1b6f3c1d2e02",
+               "id": "6",
+               "name": "Wikipedia",
+               "type": "url",
+               "url": "https://en.wikipedia.org/"
+            } ],
+            "date_added": "13350000000000000",
+            "date_modified": "13350000000000000",
+            "guid": "0d5c2a3e-5f5b-4c8e-9a53-1b6f3c1d2e03",
+            "id": "5",
+            "name": "Reading",
+            "type": "folder"
+         } ],
+         "date_added": "13350000000000000",
+         "date_modified": "13350000000000000",
+         "guid": "0bc5d13f-2cba-5d74-951f-3f233fe6c908",
+         "id": "1",
+         "name": "Bookmarks bar",
+         "type": "folder"
+      },
+      "other": {
+         "children": [  ],
+         "date_added": "13350000000000000",
+         "date_modified": "0",
+         "guid": "82b081ec-3dd3-529c-8475-ab6c344590dd",
+         "id": "2",
+         "name": "Other bookmarks",
+         "type": "folder"
+      },
+      "synced": {
+         "children": [  ],
+         "date_added": "13350000000000000",
+         "date_modified": "0",
+         "guid": "4cf2e351-0e85-532b-bb37-df045d8f8d0f",
+         "id": "3",
+         "name": "Mobile bookmarks",
+         "type": "folder"
+      }
+   },
+   "version": 1
+}"#;
+
+    #[test]
+    fn checksum_matches_chromium() {
+        let root: Value = serde_json::from_str(BOOKMARKS).unwrap();
+        assert_eq!(checksum(&root), "2e608161c17e95abbe83329ffe9c7e96");
+    }
+
+    #[test]
+    fn checksum_changes_with_titles() {
+        let mut root: Value = serde_json::from_str(BOOKMARKS).unwrap();
+        root["roots"]["bookmark_bar"]["children"][0]["name"] = json!("Rust docs");
+        assert_ne!(checksum(&root), "2e608161c17e95abbe83329ffe9c7e96");
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+use super::{slug, Bookmark, ChromiumBookmarks, Source, SurfrawFile};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// The `<!DOCTYPE NETSCAPE-Bookmark-file-1>` HTML every browser imports.
+    Netscape,
+    /// Chromium's `Bookmarks` JSON.
+    Json,
+    /// surfraw's `name url [tags...]` flat file.
+    Surfraw,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "netscape" | "html" => Ok(Format::Netscape),
+            "json" | "chromium" => Ok(Format::Json),
+            "surfraw" => Ok(Format::Surfraw),
+            _ => Err(anyhow!("Unknown bookmark format '{}'", s)),
+        }
+    }
+
+    /// Guesses the format of an import file from its first non-blank byte.
+    pub fn detect(text: &str) -> Self {
+        let text = text.trim_start();
+        if text.starts_with('{') {
+            Format::Json
+        } else if text.starts_with('<') {
+            Format::Netscape
+        } else {
+            Format::Surfraw
+        }
+    }
+}
+
+pub fn export(bookmarks: &[Bookmark], format: Format) -> Result<String> {
+    match format {
+        Format::Netscape => Ok(to_netscape(bookmarks)),
+        Format::Json => {
+            let mut tree = ChromiumBookmarks::empty();
+            tree.add_all(bookmarks)?;
+            tree.serialize()
+        }
+        Format::Surfraw => Ok(to_surfraw(bookmarks)),
+    }
+}
+
+pub fn import(text: &str) -> Result<Vec<Bookmark>> {
+    match Format::detect(text) {
+        Format::Netscape => Ok(from_netscape(text)),
+        Format::Json => Ok(ChromiumBookmarks::parse(text)?.bookmarks()),
+        Format::Surfraw => Ok(SurfrawFile::parse(PathBuf::new(), text).bookmarks()),
+    }
+}
+
+/// surfraw has no folders, so the innermost folder becomes an extra tag and
+/// names are reduced to unique single words.
+fn to_surfraw(bookmarks: &[Bookmark]) -> String {
+    let mut file = SurfrawFile::parse(PathBuf::new(), "");
+    for bookmark in bookmarks {
+        let _ = file.add(&surfraw_name(&file, bookmark), &bookmark.url, &surfraw_tags(bookmark));
+    }
+    file.to_string()
+}
+
+/// A single-word name for `bookmark` that `file` doesn't use yet.
+pub fn surfraw_name(file: &SurfrawFile, bookmark: &Bookmark) -> String {
+    let base = match bookmark.source {
+        Source::Surfraw => bookmark.name.clone(),
+        Source::Chromium { .. } => slug(&bookmark.name),
+    };
+    unique_name(file, &base)
+}
+
+/// `base`, or `base-2`, `base-3`... if `file` already has it.
+pub fn unique_name(file: &SurfrawFile, base: &str) -> String {
+    let base = if base.is_empty() { "bookmark" } else { base };
+
+    let mut name = base.to_string();
+    let mut n = 2;
+    while file.contains(&name) {
+        name = format!("{}-{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+pub fn surfraw_tags(bookmark: &Bookmark) -> Vec<String> {
+    let mut tags = bookmark.tags.clone();
+    if let Some(folder) = bookmark.folder.rsplit('/').next().map(slug) {
+        if !folder.is_empty() && !tags.contains(&folder) {
+            tags.push(folder);
+        }
+    }
+    tags
+}
+
+fn to_netscape(bookmarks: &[Bookmark]) -> String {
+    #[derive(Default)]
+    struct Folder<'a> {
+        name: String,
+        folders: Vec<Folder<'a>>,
+        items: Vec<&'a Bookmark>,
+    }
+
+    fn write(folder: &Folder, depth: usize, out: &mut String) {
+        let indent = "    ".repeat(depth);
+        out.push_str(&format!("{}<DL><p>\n", indent));
+        for child in &folder.folders {
+            out.push_str(&format!("{}    <DT><H3>{}</H3>\n", indent, escape(&child.name)));
+            write(child, depth + 1, out);
+        }
+        for item in &folder.items {
+            let tags = if item.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" TAGS=\"{}\"", escape(&item.tags.join(",")))
+            };
+            out.push_str(&format!(
+                "{}    <DT><A HREF=\"{}\"{}>{}</A>\n",
+                indent,
+                escape(&item.url),
+                tags,
+                escape(&item.name)
+            ));
+        }
+        out.push_str(&format!("{}</DL><p>\n", indent));
+    }
+
+    let mut root = Folder::default();
+    for bookmark in bookmarks {
+        let mut folder = &mut root;
+        for segment in bookmark.folder.split('/').filter(|s| !s.is_empty()) {
+            let index = match folder.folders.iter().position(|f| f.name == segment) {
+                Some(index) => index,
+                None => {
+                    folder.folders.push(Folder { name: segment.to_string(), ..Default::default() });
+                    folder.folders.len() - 1
+                }
+            };
+            folder = &mut folder.folders[index];
+        }
+        folder.items.push(bookmark);
+    }
+
+    let mut out = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Bookmarks</TITLE>\n\
+         <H1>Bookmarks</H1>\n",
+    );
+    write(&root, 0, &mut out);
+    out
+}
+
+/// A forgiving scan of Netscape bookmark HTML: `<H3>` opens a folder for the
+/// next `<DL>`, `</DL>` closes it, and each `<A HREF>` is a bookmark.
+fn from_netscape(html: &str) -> Vec<Bookmark> {
+    let mut bookmarks = Vec::new();
+    let mut folders: Vec<String> = Vec::new();
+    let mut pending_folder: Option<String> = None;
+    // Whether each open <DL> pushed a folder, so </DL> knows what to pop.
+    let mut lists: Vec<bool> = Vec::new();
+
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let Some(end) = rest.find('>') else { break };
+        let tag = &rest[1..end];
+        let name = tag
+            .split(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        rest = &rest[end + 1..];
+
+        match name.as_str() {
+            "H3" => {
+                let close = find_ci(rest, "</H3>").unwrap_or(rest.len());
+                pending_folder = Some(unescape(rest[..close].trim()));
+                rest = &rest[close..];
+            }
+            "DL" => match pending_folder.take() {
+                Some(folder) => {
+                    folders.push(folder);
+                    lists.push(true);
+                }
+                None => lists.push(false),
+            },
+            "/DL" if lists.pop() == Some(true) => {
+                folders.pop();
+            }
+            "A" => {
+                let close = find_ci(rest, "</A>").unwrap_or(rest.len());
+                let title = unescape(rest[..close].trim());
+                rest = &rest[close..];
+
+                let Some(url) = attribute(tag, "HREF") else { continue };
+                let tags = attribute(tag, "TAGS")
+                    .map(|t| t.split(',').map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()).collect())
+                    .unwrap_or_default();
+                bookmarks.push(Bookmark {
+                    name: if title.is_empty() { url.clone() } else { title },
+                    url,
+                    folder: folders.join("/"),
+                    tags,
+                    // Browser-style entry, not backed by the profile.
+                    source: Source::Chromium { id: String::new() },
+                });
+            }
+            _ => {}
+        }
+    }
+
+    bookmarks
+}
+
+/// Offset of the ASCII `needle` in `haystack`, ignoring case, compared in
+/// place so scanning a large export stays linear.
+fn find_ci(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Value of a double-quoted attribute, matched case-insensitively.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let upper = tag.to_ascii_uppercase();
+    let key = format!("{}=\"", name);
+    let start = upper.find(&key)? + key.len();
+    let len = tag[start..].find('"')?;
+    Some(unescape(&tag[start..start + len]))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
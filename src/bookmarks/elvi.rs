@@ -0,0 +1,44 @@
+use url::Url;
+use urlencoding::encode;
+use super::Bookmark;
+
+/// Search aliases in the spirit of surfraw's elvi: name, URL template with
+/// `%s` for the query, and the page opened when no query is given.
+const ELVI: &[(&str, &str, &str)] = &[
+    ("archwiki", "https://wiki.archlinux.org/index.php?search=%s", "https://wiki.archlinux.org/"),
+    ("brave", "https://search.brave.com/search?q=%s", "https://search.brave.com/"),
+    ("crates", "https://crates.io/search?q=%s", "https://crates.io/"),
+    ("ddg", "https://duckduckgo.com/?q=%s", "https://duckduckgo.com/"),
+    ("docs", "https://docs.rs/releases/search?query=%s", "https://docs.rs/"),
+    ("duckduckgo", "https://duckduckgo.com/?q=%s", "https://duckduckgo.com/"),
+    ("github", "https://github.com/search?q=%s", "https://github.com/"),
+    ("google", "https://www.google.com/search?q=%s", "https://www.google.com/"),
+    ("mdn", "https://developer.mozilla.org/search?q=%s", "https://developer.mozilla.org/"),
+    ("stack", "https://stackoverflow.com/search?q=%s", "https://stackoverflow.com/"),
+    ("wikipedia", "https://en.wikipedia.org/w/index.php?search=%s", "https://en.wikipedia.org/"),
+    ("youtube", "https://www.youtube.com/results?search_query=%s", "https://www.youtube.com/"),
+];
+
+/// The URL for `name [query...]`: a bookmark whose URL contains `%s` acts as
+/// a user-defined alias and wins over the built-in ones.
+pub fn resolve(name: &str, query: &str, bookmarks: &[Bookmark]) -> Option<String> {
+    if let Some(bookmark) = bookmarks.iter().find(|b| b.url.contains("%s") && b.answers_to(name)) {
+        return Some(expand(&bookmark.url, query));
+    }
+    let (_, template, home) = ELVI.iter().find(|(elvis, _, _)| *elvis == name)?;
+    Some(if query.is_empty() { home.to_string() } else { expand(template, query) })
+}
+
+/// Fills `%s` in `template` with `query`; without a query that is the
+/// template's site root.
+pub fn expand(template: &str, query: &str) -> String {
+    if !template.contains("%s") {
+        return template.to_string();
+    }
+    if query.is_empty() {
+        if let Ok(url) = Url::parse(&template.replace("%s", "")) {
+            return format!("{}/", url.origin().ascii_serialization());
+        }
+    }
+    template.replace("%s", &encode(query))
+}
@@ -0,0 +1,616 @@
+mod check;
+mod chromium;
+mod convert;
+mod elvi;
+mod surfraw;
+
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use crate::config::BrowserConfig;
+use crate::rofi::Dmenu;
+
+pub use check::{check, Finding, Options as CheckOptions};
+pub use chromium::{browser_running, ChromiumBookmarks};
+pub use convert::Format;
+pub use surfraw::SurfrawFile;
+
+/// rofi exit code for `-kb-custom-1`.
+const KB_EDIT: i32 = 10;
+/// rofi exit code for `-kb-custom-2`: open in a new tab even if one already shows it.
+const KB_NEW_TAB: i32 = 11;
+
+const ROFI_COLORS: [&str; 8] = [
+    "-color-window", "#000000, #000000, #000000",
+    "-color-normal", "#000000, #b3e774, #000000, #b3e774, #000000",
+    "-color-active", "#000000, #b3e774, #000000, #b3e774, #000000",
+    "-color-urgent", "#000000, #b3e774, #000000, #b3e774, #000000",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// `~/.config/surfraw/bookmarks`, keyed by name.
+    Surfraw,
+    /// The browser's own `Bookmarks` file, keyed by node id.
+    Chromium { id: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    pub name: String,
+    pub url: String,
+    /// Folder path such as `Bookmarks bar/Dev`; empty for surfraw entries.
+    pub folder: String,
+    /// Lowercased tags; any of them also works as a launcher keyword.
+    pub tags: Vec<String>,
+    pub source: Source,
+}
+
+impl Bookmark {
+    fn label(&self) -> String {
+        let mut label = match self.source {
+            Source::Surfraw => format!("{} {}", self.name, self.url),
+            Source::Chromium { .. } => format!("{} {}  [{}]", self.name, self.url, self.folder),
+        };
+        for tag in &self.tags {
+            label.push_str(&format!("  #{}", tag));
+        }
+        label
+    }
+
+    /// Whether typing `keyword` in a launcher should open this bookmark.
+    fn answers_to(&self, keyword: &str) -> bool {
+        self.name == keyword || self.tags.iter().any(|t| t == keyword)
+    }
+}
+
+/// Surfraw bookmarks followed by the browser's own, each group sorted.
+pub fn load_all(config: &BrowserConfig) -> Result<Vec<Bookmark>> {
+    let mut bookmarks = SurfrawFile::load()?.bookmarks();
+    bookmarks.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if let Ok(chromium) = ChromiumBookmarks::load(config) {
+        let mut theirs = chromium.bookmarks();
+        theirs.sort_by(|a, b| a.folder.cmp(&b.folder).then_with(|| a.name.cmp(&b.name)));
+        bookmarks.extend(theirs);
+    }
+
+    Ok(bookmarks)
+}
+
+/// Every bookmark in file order, surfraw's first, plus the surfraw lines that
+/// didn't parse as `name url`.
+pub fn load_unsorted(config: &BrowserConfig) -> Result<(Vec<Bookmark>, Vec<String>)> {
+    let surfraw = SurfrawFile::load()?;
+    let mut bookmarks = surfraw.bookmarks();
+    if let Ok(chromium) = ChromiumBookmarks::load(config) {
+        bookmarks.extend(chromium.bookmarks());
+    }
+    Ok((bookmarks, surfraw.malformed()))
+}
+
+pub async fn show_bookmarks(incognito: bool, config: &BrowserConfig) -> Result<()> {
+    show_filtered(None, incognito, config).await
+}
+
+async fn show_filtered(mut tag: Option<String>, incognito: bool, config: &BrowserConfig) -> Result<()> {
+    loop {
+        let bookmarks: Vec<Bookmark> = load_all(config)?
+            .into_iter()
+            .filter(|b| tag.as_ref().is_none_or(|t| b.tags.contains(t)))
+            .collect();
+
+        let mut headers = vec!["- Bookmark current tab", "- Tags…", "- Open folder…"];
+        if tag.is_some() {
+            headers.extend(["- Open all", "- All bookmarks"]);
+        }
+        let mut menu = String::new();
+        for line in &headers {
+            menu.push_str(line);
+            menu.push('\n');
+        }
+        for bookmark in &bookmarks {
+            menu.push_str(&bookmark.label());
+            menu.push('\n');
+        }
+
+        let prompt = match &tag {
+            Some(tag) => format!("bookmarks #{}:", tag),
+            None => "bookmarks:".to_string(),
+        };
+        let (code, index, text) = rofi_bookmarks(&prompt, &menu)?;
+        let Some(index) = index else {
+            // Custom input: treat it as a keyword.
+            if !text.is_empty() && !open_keyword(&text, incognito, config).await? {
+                crate::rofi_message(&format!("No bookmark or search alias '{}'", text));
+            }
+            return Ok(());
+        };
+
+        match headers.get(index).copied() {
+            Some("- Bookmark current tab") => {
+                if let Err(e) = add_current_tab(None, &[], config).await {
+                    crate::rofi_message(&e.to_string());
+                }
+                continue;
+            }
+            Some("- Tags…") => {
+                let (_, chosen) = dmenu("Tag").run(&tag_menu(&load_all(config)?))?;
+                if let Some(chosen) = chosen.split_whitespace().next() {
+                    tag = Some(chosen.trim_start_matches('#').to_string());
+                }
+                continue;
+            }
+            Some("- Open folder…") => {
+                let all = load_all(config)?;
+                let labels: Vec<String> = groups(&all).into_iter().map(|(label, _)| label).collect();
+                let (_, chosen) = dmenu("Open folder").run(&labels.join("\n"))?;
+                if chosen.is_empty() {
+                    continue;
+                }
+                return open_group(&chosen, None, config).await;
+            }
+            Some("- Open all") => {
+                let label = format!("#{}", tag.as_deref().unwrap_or_default());
+                return open_group(&label, None, config).await;
+            }
+            Some(_) => {
+                tag = None;
+                continue;
+            }
+            None => {}
+        }
+
+        let Some(bookmark) = bookmarks.get(index - headers.len()) else { return Ok(()) };
+
+        if code == KB_EDIT {
+            if let Err(e) = edit(bookmark, config) {
+                crate::rofi_message(&e.to_string());
+            }
+            continue;
+        }
+
+        open(bookmark, incognito, code == KB_NEW_TAB, config).await?;
+        return Ok(());
+    }
+}
+
+async fn open(bookmark: &Bookmark, incognito: bool, new_tab: bool, config: &BrowserConfig) -> Result<()> {
+    open_url(&elvi::expand(&bookmark.url, ""), incognito, new_tab, config).await
+}
+
+/// Regular bookmarks switch to a tab already showing them unless `new_tab`.
+async fn open_url(url: &str, incognito: bool, new_tab: bool, config: &BrowserConfig) -> Result<()> {
+    if !incognito {
+        return crate::switch_or_open(url, new_tab, config).await;
+    }
+    let watch = crate::focus::Watch::start(config).await;
+    crate::open_incognito(&[url.to_string()], config).await?;
+    watch.focus_next().await;
+    Ok(())
+}
+
+/// Opens `keyword [query...]`: with a query, the search alias of that name;
+/// otherwise the bookmark named or tagged `keyword` (several matches open a
+/// submenu of just that tag), falling back to the alias's home page.
+/// Returns `false` when nothing matches.
+pub async fn open_keyword(input: &str, incognito: bool, config: &BrowserConfig) -> Result<bool> {
+    let input = input.trim();
+    let (keyword, query) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    let keyword = keyword.trim_start_matches('#').to_lowercase();
+    let query = query.trim();
+    let all = load_all(config)?;
+
+    let matches: Vec<&Bookmark> = if query.is_empty() {
+        all.iter().filter(|b| b.answers_to(&keyword)).collect()
+    } else {
+        Vec::new()
+    };
+
+    match matches.as_slice() {
+        [] => match elvi::resolve(&keyword, query, &all) {
+            Some(url) => {
+                open_url(&url, incognito, false, config).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        },
+        [bookmark] => {
+            open(bookmark, incognito, false, config).await?;
+            Ok(true)
+        }
+        _ => {
+            Box::pin(show_filtered(Some(keyword), incognito, config)).await?;
+            Ok(true)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpenMode {
+    Tabs,
+    Window,
+    Incognito,
+}
+
+/// Every opening target: browser folders as `[path]` and tags as `#tag`,
+/// each with the bookmarks it contains.
+fn groups(bookmarks: &[Bookmark]) -> Vec<(String, Vec<&Bookmark>)> {
+    let mut groups: Vec<(String, Vec<&Bookmark>)> = Vec::new();
+    let mut push = |label: String, bookmark| match groups.iter_mut().find(|(l, _)| *l == label) {
+        Some((_, members)) => members.push(bookmark),
+        None => groups.push((label, vec![bookmark])),
+    };
+
+    for bookmark in bookmarks {
+        if !bookmark.folder.is_empty() {
+            push(format!("[{}]", bookmark.folder), bookmark);
+        }
+        for tag in &bookmark.tags {
+            push(format!("#{}", tag), bookmark);
+        }
+    }
+    groups
+}
+
+/// Opens every bookmark in the folder or tag named by `label` (`[path]` or
+/// `#tag`; a bare name tries the folder first), asking how unless `mode` is
+/// given and confirming when there are more than `config.open_all_confirm`.
+pub async fn open_group(label: &str, mode: Option<OpenMode>, config: &BrowserConfig) -> Result<()> {
+    let all = load_all(config)?;
+    let wanted = if label.starts_with('[') || label.starts_with('#') {
+        vec![label.to_string()]
+    } else {
+        vec![format!("[{}]", label), format!("#{}", label)]
+    };
+    let groups = groups(&all);
+    let urls: Vec<String> = wanted
+        .iter()
+        .find_map(|w| groups.iter().find(|(l, _)| l.eq_ignore_ascii_case(w)))
+        .map(|(_, members)| members.iter().map(|b| b.url.clone()).collect())
+        .ok_or_else(|| anyhow!("No bookmark folder or tag '{}'", label))?;
+
+    let mode = match mode {
+        Some(mode) => mode,
+        None => {
+            let (_, choice) = dmenu(&format!("Open {} bookmarks", urls.len()))
+                .run("In tabs\nIn a new window\nIn incognito")?;
+            match choice.as_str() {
+                "In tabs" => OpenMode::Tabs,
+                "In a new window" => OpenMode::Window,
+                "In incognito" => OpenMode::Incognito,
+                _ => return Ok(()),
+            }
+        }
+    };
+
+    if urls.len() > config.open_all_confirm
+        && crate::rofi_confirm(&format!("Open {} tabs?", urls.len())) != "YES"
+    {
+        return Ok(());
+    }
+
+    let watch = crate::focus::Watch::start(config).await;
+    match mode {
+        OpenMode::Tabs => {
+            for url in &urls {
+                crate::open_tab(url, config).await?;
+            }
+        }
+        OpenMode::Window => crate::open_window(&urls, config).await?,
+        OpenMode::Incognito => crate::open_incognito(&urls, config).await?,
+    }
+    watch.focus_next().await;
+    Ok(())
+}
+
+/// `tag (count)` lines, most used first.
+fn tag_menu(bookmarks: &[Bookmark]) -> String {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for tag in bookmarks.iter().flat_map(|b| &b.tags) {
+        match counts.iter_mut().find(|(t, _)| t == tag) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((tag.clone(), 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+        .iter()
+        .map(|(tag, n)| format!("#{} ({})", tag, n))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Rename / move / copy / delete submenu for a single bookmark.
+fn edit(bookmark: &Bookmark, config: &BrowserConfig) -> Result<()> {
+    let mut actions = vec!["Rename", "Copy link"];
+    if matches!(bookmark.source, Source::Chromium { .. }) {
+        actions.push("Move to folder");
+    }
+    actions.push("Delete");
+
+    let (_, choice) = dmenu(&format!("Edit {}", bookmark.name)).run(&actions.join("\n"))?;
+    match choice.as_str() {
+        "Rename" => {
+            let (_, name) = Dmenu { filter: Some(&bookmark.name), ..dmenu("New name") }.run("")?;
+            if !name.is_empty() && name != bookmark.name {
+                rename(bookmark, &name, config)?;
+            }
+        }
+        "Copy link" => crate::clipboard::choose_and_copy(&bookmark.name, &elvi::expand(&bookmark.url, ""))?,
+        "Move to folder" => {
+            let folders = ChromiumBookmarks::load(config)?.folders().join("\n");
+            let (_, folder) = dmenu("Move to").run(&folders)?;
+            if !folder.is_empty() {
+                move_to(bookmark, &folder, config)?;
+            }
+        }
+        "Delete" if crate::rofi_confirm(&format!("Delete {}?", bookmark.name)) == "YES" => {
+            delete(bookmark, config)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Adds the focused tab to the surfraw file, prompting for a name unless given.
+pub async fn add_current_tab(
+    name: Option<String>,
+    tags: &[String],
+    config: &BrowserConfig,
+) -> Result<()> {
+    let tabs = crate::get_tabs(config).await?;
+    let tab = crate::active_tab(&tabs, config).ok_or_else(|| anyhow!("No open tab to bookmark"))?;
+
+    let name = match name {
+        Some(name) => name,
+        None => Dmenu { filter: Some(&slug(&tab.title)), ..dmenu("Bookmark name") }.run("")?.1,
+    };
+    if name.is_empty() {
+        return Ok(());
+    }
+
+    let mut file = SurfrawFile::load()?;
+    file.add(&name, &tab.url, tags)?;
+    file.save()
+}
+
+pub fn rename(bookmark: &Bookmark, name: &str, config: &BrowserConfig) -> Result<()> {
+    match &bookmark.source {
+        Source::Surfraw => {
+            let mut file = SurfrawFile::load()?;
+            file.rename(&bookmark.name, name)?;
+            file.save()
+        }
+        Source::Chromium { id } => {
+            let mut file = writable_chromium(config)?;
+            file.rename(id, name)?;
+            file.save()
+        }
+    }
+}
+
+pub fn move_to(bookmark: &Bookmark, folder: &str, config: &BrowserConfig) -> Result<()> {
+    match &bookmark.source {
+        Source::Surfraw => Err(anyhow!("surfraw bookmarks have no folders")),
+        Source::Chromium { id } => {
+            let mut file = writable_chromium(config)?;
+            file.move_to(id, folder)?;
+            file.save()
+        }
+    }
+}
+
+pub fn delete(bookmark: &Bookmark, config: &BrowserConfig) -> Result<()> {
+    match &bookmark.source {
+        Source::Surfraw => {
+            let mut file = SurfrawFile::load()?;
+            file.remove(&bookmark.name)?;
+            file.save()
+        }
+        Source::Chromium { id } => {
+            let mut file = writable_chromium(config)?;
+            file.remove(id)?;
+            file.save()
+        }
+    }
+}
+
+pub fn set_url(bookmark: &Bookmark, url: &str, config: &BrowserConfig) -> Result<()> {
+    match &bookmark.source {
+        Source::Surfraw => {
+            let mut file = SurfrawFile::load()?;
+            file.set_url(&bookmark.name, url)?;
+            file.save()
+        }
+        Source::Chromium { id } => {
+            let mut file = writable_chromium(config)?;
+            file.set_url(id, url)?;
+            file.save()
+        }
+    }
+}
+
+/// Walks through `bbr bookmarks check` findings in rofi, removing or editing
+/// each one picked until none are left or the menu is dismissed.
+pub fn review(mut findings: Vec<Finding>, config: &BrowserConfig) -> Result<()> {
+    while !findings.is_empty() {
+        let menu = findings.iter().map(Finding::label).collect::<Vec<_>>().join("\n");
+        let (code, choice) = dmenu(&format!("{} problems", findings.len())).run(&menu)?;
+        if code != 0 {
+            return Ok(());
+        }
+        let Some(index) = findings.iter().position(|f| f.label() == choice) else {
+            continue;
+        };
+
+        match fix(&findings[index], config) {
+            Ok(true) => {
+                findings.remove(index);
+            }
+            Ok(false) => {}
+            Err(e) => crate::rofi_message(&e.to_string()),
+        }
+    }
+    Ok(())
+}
+
+/// Offers the fixes for one finding; returns whether it was dealt with.
+fn fix(finding: &Finding, config: &BrowserConfig) -> Result<bool> {
+    let (_, action) = dmenu(&finding.label()).run("Remove\nEdit\nSkip")?;
+    match (action.as_str(), &finding.subject) {
+        ("Remove", check::Subject::Bookmark(bookmark)) => delete(bookmark, config)?,
+        ("Remove", check::Subject::Line(line)) => {
+            let mut file = SurfrawFile::load()?;
+            file.replace_line(line, None)?;
+            file.save()?;
+        }
+        ("Edit", check::Subject::Bookmark(bookmark)) => {
+            let (_, url) = Dmenu { filter: Some(&bookmark.url), ..dmenu("URL") }.run("")?;
+            if url.is_empty() || url == bookmark.url {
+                return Ok(false);
+            }
+            set_url(bookmark, &url, config)?;
+        }
+        ("Edit", check::Subject::Line(line)) => {
+            let (_, text) = Dmenu { filter: Some(line), ..dmenu("name url [tags]") }.run("")?;
+            if text.is_empty() || text == *line {
+                return Ok(false);
+            }
+            let mut file = SurfrawFile::load()?;
+            file.replace_line(line, Some(&text))?;
+            file.save()?;
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Finds a bookmark by name for the command line, preferring surfraw's.
+pub fn find(name: &str, config: &BrowserConfig) -> Result<Bookmark> {
+    load_all(config)?
+        .into_iter()
+        .find(|b| b.name == name)
+        .ok_or_else(|| anyhow!("No bookmark named '{}'", name))
+}
+
+/// Adds `(title, url)` pages to the surfraw file under one tag made from
+/// `name`, so `open-all '#tag'` reopens them together; entries the file
+/// already has for a URL get the tag instead of a duplicate. Returns the tag
+/// and how many bookmarks now carry it for these pages.
+pub fn save_tabs(name: &str, pages: &[(String, String)]) -> Result<(String, usize)> {
+    let tag = match slug(name) {
+        tag if tag.is_empty() => "session".to_string(),
+        tag => tag,
+    };
+    let mut file = SurfrawFile::load()?;
+    let mut saved: Vec<String> = Vec::new();
+    for (title, url) in pages {
+        let known = file.bookmarks().into_iter().find(|b| b.url == *url);
+        let name = match known {
+            Some(bookmark) => {
+                file.add_tag(&bookmark.name, &tag)?;
+                bookmark.name
+            }
+            None => {
+                let name = convert::unique_name(&file, &slug(title));
+                file.add(&name, url, std::slice::from_ref(&tag))?;
+                name
+            }
+        };
+        if !saved.contains(&name) {
+            saved.push(name);
+        }
+    }
+    file.save()?;
+    Ok((tag, saved.len()))
+}
+
+/// Where `bbr bookmarks import` writes to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportTarget {
+    Surfraw,
+    Browser,
+}
+
+/// Every bookmark from both stores in `format`.
+pub fn export(format: Format, config: &BrowserConfig) -> Result<String> {
+    convert::export(&load_all(config)?, format)
+}
+
+/// Adds the bookmarks in `path` (any supported format) to `into`, skipping
+/// URLs it already has. Returns how many were added.
+pub fn import(path: &str, into: ImportTarget, config: &BrowserConfig) -> Result<usize> {
+    let incoming = convert::import(&std::fs::read_to_string(path)?)?;
+
+    match into {
+        ImportTarget::Surfraw => {
+            let mut file = SurfrawFile::load()?;
+            let mut known: HashSet<String> = file.bookmarks().into_iter().map(|b| b.url).collect();
+            let mut added = 0;
+            for bookmark in incoming {
+                if known.contains(&bookmark.url) {
+                    continue;
+                }
+                let name = convert::surfraw_name(&file, &bookmark);
+                file.add(&name, &bookmark.url, &convert::surfraw_tags(&bookmark))?;
+                known.insert(bookmark.url);
+                added += 1;
+            }
+            file.save()?;
+            Ok(added)
+        }
+        ImportTarget::Browser => {
+            let mut file = writable_chromium(config)?;
+            let mut known: HashSet<String> = file.bookmarks().into_iter().map(|b| b.url).collect();
+            let mut fresh = Vec::new();
+            for bookmark in incoming {
+                if known.insert(bookmark.url.clone()) {
+                    fresh.push(bookmark);
+                }
+            }
+            file.add_all(&fresh)?;
+            file.save()?;
+            Ok(fresh.len())
+        }
+    }
+}
+
+fn writable_chromium(config: &BrowserConfig) -> Result<ChromiumBookmarks> {
+    if chromium::browser_running(config) {
+        return Err(anyhow!(
+            "Close {} to edit its bookmarks; it would overwrite the changes",
+            config.name
+        ));
+    }
+    ChromiumBookmarks::load(config)
+}
+
+/// A single-word surfraw name derived from a page title.
+fn slug(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .take(3)
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// The main bookmarks list; returns rofi's exit code, the selected row (or
+/// `None` for custom input) and the text.
+fn rofi_bookmarks(prompt: &str, menu: &str) -> Result<(i32, Option<usize>, String)> {
+    let (code, selection) = Dmenu {
+        mesg: Some(">>> Alt+1: rename, move, copy or delete · Alt+Enter: open in a new tab · type a tag or name and Ctrl+Enter to open it"),
+        keys: &["Alt+1", "Alt+Return"],
+        format: Some("i s"),
+        ..dmenu(prompt)
+    }
+    .run(menu)?;
+    let (index, text) = selection.split_once(' ').unwrap_or((&selection, ""));
+    Ok((code, index.parse().ok(), text.to_string()))
+}
+
+/// The coloured, case-insensitive list every bookmarks menu uses.
+fn dmenu(prompt: &str) -> Dmenu<'_> {
+    Dmenu { prompt, ignore_case: true, theme: &ROFI_COLORS, ..Default::default() }
+}
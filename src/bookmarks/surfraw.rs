@@ -0,0 +1,220 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::PathBuf;
+use super::{Bookmark, Source};
+
+enum Line {
+    Entry { name: String, url: String, rest: String },
+    /// Comments, blank lines and anything else we don't understand; written
+    /// back untouched.
+    Raw(String),
+}
+
+/// `~/.config/surfraw/bookmarks`, one `name url [tags...]` entry per line.
+pub struct SurfrawFile {
+    path: PathBuf,
+    lines: Vec<Line>,
+}
+
+impl SurfrawFile {
+    pub fn path() -> Result<PathBuf> {
+        Ok(PathBuf::from(std::env::var("HOME")?).join(".config/surfraw/bookmarks"))
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self::parse(path, &content))
+    }
+
+    pub fn parse(path: PathBuf, content: &str) -> Self {
+        let lines = content.lines().map(parse_line).collect();
+        Self { path, lines }
+    }
+
+    pub fn bookmarks(&self) -> Vec<Bookmark> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Entry { name, url, rest } => Some(Bookmark {
+                    name: name.clone(),
+                    url: url.clone(),
+                    folder: String::new(),
+                    tags: parse_tags(rest),
+                    source: Source::Surfraw,
+                }),
+                Line::Raw(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.lines
+            .iter()
+            .any(|line| matches!(line, Line::Entry { name: n, .. } if n == name))
+    }
+
+    pub fn add(&mut self, name: &str, url: &str, tags: &[String]) -> Result<()> {
+        check_name(name)?;
+        if self.contains(name) {
+            return Err(anyhow!("A bookmark named '{}' already exists", name));
+        }
+        self.lines.push(Line::Entry {
+            name: name.to_string(),
+            url: url.to_string(),
+            rest: tags.join(" "),
+        });
+        Ok(())
+    }
+
+    pub fn rename(&mut self, name: &str, new_name: &str) -> Result<()> {
+        check_name(new_name)?;
+        if self.contains(new_name) {
+            return Err(anyhow!("A bookmark named '{}' already exists", new_name));
+        }
+        match self.entry_mut(name)? {
+            Line::Entry { name, .. } => *name = new_name.to_string(),
+            Line::Raw(_) => unreachable!(),
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        let before = self.lines.len();
+        self.lines
+            .retain(|line| !matches!(line, Line::Entry { name: n, .. } if n == name));
+        if self.lines.len() == before {
+            return Err(anyhow!("No bookmark named '{}'", name));
+        }
+        Ok(())
+    }
+
+    pub fn set_url(&mut self, name: &str, new_url: &str) -> Result<()> {
+        match self.entry_mut(name)? {
+            Line::Entry { url, .. } => *url = new_url.to_string(),
+            Line::Raw(_) => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Adds `tag` to the entry named `name` unless it already has it.
+    pub fn add_tag(&mut self, name: &str, tag: &str) -> Result<()> {
+        match self.entry_mut(name)? {
+            Line::Entry { rest, .. } if parse_tags(rest).iter().any(|t| t == tag) => {}
+            Line::Entry { rest, .. } if rest.is_empty() => *rest = tag.to_string(),
+            Line::Entry { rest, .. } => *rest = format!("{} {}", rest, tag),
+            Line::Raw(_) => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Lines that are neither entries, comments nor blank, so `surfraw -print`
+    /// can't do anything with them.
+    pub fn malformed(&self) -> Vec<String> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Raw(raw) if !raw.trim().is_empty() && !is_comment(raw) => Some(raw.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replaces the first raw line equal to `text`, or drops it if `with` is `None`.
+    pub fn replace_line(&mut self, text: &str, with: Option<&str>) -> Result<()> {
+        let index = self
+            .lines
+            .iter()
+            .position(|line| matches!(line, Line::Raw(raw) if raw == text))
+            .ok_or_else(|| anyhow!("No line '{}'", text))?;
+        match with {
+            Some(with) => self.lines[index] = parse_line(with),
+            None => {
+                self.lines.remove(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the file back via a temporary file so a crash never truncates it.
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, self.to_string())?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    fn entry_mut(&mut self, name: &str) -> Result<&mut Line> {
+        self.lines
+            .iter_mut()
+            .find(|line| matches!(line, Line::Entry { name: n, .. } if n == name))
+            .ok_or_else(|| anyhow!("No bookmark named '{}'", name))
+    }
+}
+
+impl std::fmt::Display for SurfrawFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut content = String::new();
+        for line in &self.lines {
+            match line {
+                Line::Entry { name, url, rest } if rest.is_empty() => {
+                    content.push_str(&format!("{} {}\n", name, url));
+                }
+                Line::Entry { name, url, rest } => {
+                    content.push_str(&format!("{} {} {}\n", name, url, rest));
+                }
+                Line::Raw(raw) => {
+                    content.push_str(raw);
+                    content.push('\n');
+                }
+            }
+        }
+        f.write_str(&content)
+    }
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with('#') || line.starts_with('/')
+}
+
+fn parse_line(line: &str) -> Line {
+    if is_comment(line) {
+        return Line::Raw(line.to_string());
+    }
+
+    let mut parts = line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(url)) => Line::Entry {
+            name: name.to_string(),
+            url: url.to_string(),
+            rest: parts.collect::<Vec<_>>().join(" "),
+        },
+        _ => Line::Raw(line.to_string()),
+    }
+}
+
+/// Tags follow the URL, separated by whitespace or commas; a leading `#` or
+/// `:` is accepted and dropped.
+fn parse_tags(rest: &str) -> Vec<String> {
+    rest.split(|c: char| c.is_whitespace() || c == ',')
+        .map(|tag| tag.trim_start_matches(['#', ':']))
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// surfraw looks bookmarks up by their first word, so names can't contain spaces.
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(anyhow!("Bookmark names must be a single word, got '{}'", name));
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds between the Windows epoch (1601) used by Chromium and the Unix epoch.
+const WINDOWS_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// Converts a Unix timestamp to Chromium's microseconds since 1601.
+pub fn from_unix(unix: i64) -> i64 {
+    (unix + WINDOWS_EPOCH_OFFSET) * 1_000_000
+}
+
+/// Converts Chromium's microseconds since 1601 to a Unix timestamp.
+pub fn to_unix(chrome: i64) -> i64 {
+    chrome / 1_000_000 - WINDOWS_EPOCH_OFFSET
+}
+
+/// The current time as microseconds since 1601.
+pub fn now() -> i64 {
+    let unix = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    from_unix(unix.as_secs() as i64) + i64::from(unix.subsec_micros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unix_epoch_is_chromium_offset() {
+        assert_eq!(from_unix(0), 11_644_473_600_000_000);
+        assert_eq!(to_unix(11_644_473_600_000_000), 0);
+    }
+
+    #[test]
+    fn round_trips_whole_seconds() {
+        // 2024-01-01T00:00:00Z
+        assert_eq!(from_unix(1_704_067_200), 13_348_540_800_000_000);
+        assert_eq!(to_unix(from_unix(1_704_067_200)), 1_704_067_200);
+        assert_eq!(to_unix(13_348_540_800_999_999), 1_704_067_200);
+    }
+
+    #[test]
+    fn now_is_after_unix_now() {
+        let unix = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        assert!(to_unix(now()) >= unix);
+    }
+}
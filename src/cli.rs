@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Result};
+use crate::bookmarks::{CheckOptions, Format, ImportTarget, OpenMode};
+use crate::clipboard;
+use std::time::Duration;
+use crate::history::{HistoryFilter, Since};
+
+/// What the binary was asked to do on the command line.
+#[derive(Debug)]
+pub enum Cmd {
+    /// No arguments: show the main tab menu.
+    Menu,
+    /// `history [--since <dur>] [--domain <host>] [--typed-only] [--search <words>]`
+    History(HistoryFilter),
+    /// `bookmarks [add|rename|move|delete ...]`
+    Bookmarks(BookmarksCmd),
+    /// `index-pages`: capture page text into the local search index.
+    IndexPages,
+    /// `find [<words>...]`: search captured page text.
+    Find(Option<String>),
+    /// `copy [--active] [--format <fmt>]`: copy the focused tab's link.
+    Copy(clipboard::Format),
+    /// `open [--new-tab] --clipboard|--primary|<text>`: open a link or
+    /// search, switching to a tab that already shows it unless `true`.
+    Open(OpenFrom, bool),
+    Tabs(TabsCmd),
+}
+
+#[derive(Debug)]
+pub enum TabsCmd {
+    /// Suspend background tabs, only those idle for at least this many seconds if given.
+    Suspend(Option<i64>),
+}
+
+#[derive(Debug)]
+pub enum OpenFrom {
+    Selection(clipboard::Selection),
+    Text(String),
+}
+
+#[derive(Debug)]
+pub enum BookmarksCmd {
+    Menu,
+    /// Bookmark the focused tab, optionally under the given name and tags.
+    Add(Option<String>, Vec<String>),
+    /// Open the bookmark with this name or tag.
+    Open(String),
+    /// Open every bookmark in a browser folder or surfraw tag.
+    OpenAll(String, Option<OpenMode>),
+    Rename(String, String),
+    Move(String, String),
+    Delete(String),
+    /// Write every bookmark in a format, to a file or stdout.
+    Export(Format, Option<String>),
+    Import(String, ImportTarget),
+    /// Report duplicate, malformed and dead bookmarks; `true` to fix them in rofi.
+    Check(CheckOptions, bool),
+}
+
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Cmd> {
+    let mut args = args.into_iter();
+
+    match args.next().as_deref() {
+        None => Ok(Cmd::Menu),
+        Some("history") => parse_history(args),
+        Some("bookmarks") => parse_bookmarks(args),
+        Some("index-pages") => Ok(Cmd::IndexPages),
+        Some("find") => {
+            let words = args.collect::<Vec<_>>().join(" ");
+            Ok(Cmd::Find((!words.is_empty()).then_some(words)))
+        }
+        Some("copy") => parse_copy(args),
+        Some("tabs") => parse_tabs(args),
+        Some("open") => {
+            let mut words: Vec<String> = args.collect();
+            let new_tab = words.first().is_some_and(|w| w == "--new-tab");
+            if new_tab {
+                words.remove(0);
+            }
+            let from = match words.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+                ["--clipboard"] => OpenFrom::Selection(clipboard::Selection::Clipboard),
+                ["--primary"] => OpenFrom::Selection(clipboard::Selection::Primary),
+                [] => return Err(anyhow!("Usage: bbr open [--new-tab] --clipboard|--primary|<url or words>")),
+                _ => OpenFrom::Text(words.join(" ")),
+            };
+            Ok(Cmd::Open(from, new_tab))
+        }
+        Some(other) => Err(anyhow!("Unknown command '{}'\n\n{}", other, USAGE)),
+    }
+}
+
+fn parse_history(mut args: impl Iterator<Item = String>) -> Result<Cmd> {
+    let mut filter = HistoryFilter::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--since" => filter.since = Some(Since::parse(&value(&mut args, &arg)?)?),
+            "--domain" => filter.domain = Some(value(&mut args, &arg)?),
+            "--typed-only" => filter.typed_only = true,
+            "--search" => filter.query = Some(value(&mut args, &arg)?),
+            _ => return Err(anyhow!("Unknown history option '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(Cmd::History(filter))
+}
+
+fn parse_tabs(mut args: impl Iterator<Item = String>) -> Result<Cmd> {
+    match args.next().as_deref() {
+        Some("suspend") => {}
+        _ => return Err(anyhow!("Usage: bbr tabs suspend [--idle <dur>]")),
+    }
+    let idle = match (args.next().as_deref(), args.next()) {
+        (None, _) => None,
+        (Some("--idle"), Some(dur)) => match Since::parse(&dur)? {
+            Since::Ago { secs, .. } => Some(secs),
+            _ => return Err(anyhow!("--idle takes a duration such as 30m, 2h or 1d")),
+        },
+        _ => return Err(anyhow!("Usage: bbr tabs suspend [--idle <dur>]")),
+    };
+    Ok(Cmd::Tabs(TabsCmd::Suspend(idle)))
+}
+
+fn parse_copy(mut args: impl Iterator<Item = String>) -> Result<Cmd> {
+    let mut format = clipboard::Format::Plain;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // The focused tab is the only source for now; accepted for clarity in scripts.
+            "--active" => {}
+            "--format" => format = clipboard::Format::parse(&value(&mut args, &arg)?)?,
+            _ => return Err(anyhow!("Unknown copy option '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(Cmd::Copy(format))
+}
+
+fn parse_bookmarks(mut args: impl Iterator<Item = String>) -> Result<Cmd> {
+    let cmd = match args.next().as_deref() {
+        None => BookmarksCmd::Menu,
+        Some("add") => {
+            let name = args.next();
+            BookmarksCmd::Add(name, args.collect())
+        }
+        Some("open") => BookmarksCmd::Open(value(&mut args, "open")?),
+        Some("open-all") => {
+            let group = value(&mut args, "open-all")?;
+            let mode = match args.next().as_deref() {
+                None => None,
+                Some("--tabs") => Some(OpenMode::Tabs),
+                Some("--window") => Some(OpenMode::Window),
+                Some("--incognito") => Some(OpenMode::Incognito),
+                Some(other) => return Err(anyhow!("Unknown open-all option '{}'\n\n{}", other, USAGE)),
+            };
+            BookmarksCmd::OpenAll(group, mode)
+        }
+        Some("rename") => BookmarksCmd::Rename(
+            value(&mut args, "rename")?,
+            value(&mut args, "rename")?,
+        ),
+        Some("move") => BookmarksCmd::Move(value(&mut args, "move")?, value(&mut args, "move")?),
+        Some("delete") => BookmarksCmd::Delete(value(&mut args, "delete")?),
+        Some("export") => {
+            let mut format = Format::Netscape;
+            let mut output = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => format = Format::parse(&value(&mut args, &arg)?)?,
+                    "-o" | "--output" => output = Some(value(&mut args, &arg)?),
+                    _ => return Err(anyhow!("Unknown export option '{}'\n\n{}", arg, USAGE)),
+                }
+            }
+            BookmarksCmd::Export(format, output)
+        }
+        Some("import") => {
+            let file = value(&mut args, "import")?;
+            let into = match (args.next().as_deref(), args.next().as_deref()) {
+                (None, _) | (Some("--into"), Some("surfraw")) => ImportTarget::Surfraw,
+                (Some("--into"), Some("browser")) => ImportTarget::Browser,
+                _ => return Err(anyhow!("Usage: bbr bookmarks import <file> [--into surfraw|browser]")),
+            };
+            BookmarksCmd::Import(file, into)
+        }
+        Some("check") => {
+            let mut options = CheckOptions { offline: false, timeout: Duration::from_secs(10) };
+            let mut fix = false;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--fix" => fix = true,
+                    "--offline" => options.offline = true,
+                    "--timeout" => {
+                        let secs = value(&mut args, &arg)?;
+                        options.timeout = Duration::from_secs(
+                            secs.parse().map_err(|_| anyhow!("--timeout takes seconds, got '{}'", secs))?,
+                        );
+                    }
+                    _ => return Err(anyhow!("Unknown check option '{}'\n\n{}", arg, USAGE)),
+                }
+            }
+            BookmarksCmd::Check(options, fix)
+        }
+        Some(other) => return Err(anyhow!("Unknown bookmarks command '{}'\n\n{}", other, USAGE)),
+    };
+    Ok(Cmd::Bookmarks(cmd))
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    args.next().ok_or_else(|| anyhow!("{} requires a value", flag))
+}
+
+const USAGE: &str = "\
+Usage:
+  bbr                                 Show the tab menu
+  bbr history [--since <dur>] [--domain <host>] [--typed-only] [--search <words>]
+                                      Browse history (dur: 30m, 2h, 2d, 1w, today, week)
+  bbr bookmarks                       Browse bookmarks
+  bbr bookmarks add [<name> [<tag>...]]
+                                      Bookmark the focused tab
+  bbr bookmarks open <keyword>        Open the bookmark with this name or tag
+  bbr bookmarks open-all <folder|#tag> [--tabs|--window|--incognito]
+                                      Open every bookmark in a folder or tag
+  bbr bookmarks rename <name> <new>   Rename a bookmark
+  bbr bookmarks move <name> <folder>  Move a browser bookmark to another folder
+  bbr bookmarks delete <name>         Delete a bookmark
+  bbr bookmarks export [--format netscape|json|surfraw] [-o <file>]
+                                      Export all bookmarks (default: netscape)
+  bbr bookmarks import <file> [--into surfraw|browser]
+                                      Import bookmarks from any of those formats
+  bbr bookmarks check [--fix] [--offline] [--timeout <secs>]
+                                      Report duplicate, malformed and dead
+                                      bookmarks; --fix reviews them in rofi
+  bbr index-pages                     Capture the text of pages as they load
+  bbr find [<words>...]               Search captured page text
+  bbr copy [--active] [--format plain|md|org|html]
+                                      Copy the focused tab's link (default: plain)
+  bbr open [--new-tab] --clipboard|--primary|<url or words>
+                                      Open a link, or search for the text,
+                                      switching to its tab if already open
+  bbr tabs suspend [--idle <dur>]     Replace background tabs (idle that long)
+                                      with placeholders until they are shown";
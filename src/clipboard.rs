@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Clipboard writers we know: the arguments that make them read stdin into
+/// the clipboard selection, and the flag for a MIME type if they have one.
+const WRITERS: [(&str, &[&str], Option<&str>); 3] = [
+    ("wl-copy", &[], Some("--type")),
+    ("xclip", &["-selection", "clipboard"], Some("-t")),
+    ("xsel", &["--clipboard", "--input"], None),
+];
+
+/// Clipboard readers, with the arguments for the clipboard and the primary
+/// selection.
+const READERS: [(&str, &[&str], &[&str]); 3] = [
+    ("wl-paste", &["--no-newline"], &["--no-newline", "--primary"]),
+    ("xclip", &["-o", "-selection", "clipboard"], &["-o", "-selection", "primary"]),
+    ("xsel", &["--output", "--clipboard"], &["--output", "--primary"]),
+];
+
+/// Which X/Wayland selection to read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    Clipboard,
+    /// Whatever text was last highlighted.
+    Primary,
+}
+
+/// How a page is written to the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Plain,
+    Markdown,
+    Org,
+    Html,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Plain, Format::Markdown, Format::Org, Format::Html];
+
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "plain" | "url" => Ok(Format::Plain),
+            "md" | "markdown" => Ok(Format::Markdown),
+            "org" => Ok(Format::Org),
+            "html" => Ok(Format::Html),
+            _ => Err(anyhow!("Unknown copy format '{}' (plain, md, org or html)", s)),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Plain => "URL",
+            Format::Markdown => "Markdown",
+            Format::Org => "Org",
+            Format::Html => "HTML",
+        }
+    }
+
+    /// The link to `url` titled `title`; an empty title falls back to the URL.
+    pub fn link(self, title: &str, url: &str) -> String {
+        let title = if title.is_empty() { url } else { title };
+        match self {
+            Format::Plain => url.to_string(),
+            Format::Markdown => format!("[{}]({})", title.replace('[', "\\[").replace(']', "\\]"), url),
+            Format::Org => format!("[[{}][{}]]", url, title.replace('[', "{").replace(']', "}")),
+            Format::Html => format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(title)),
+        }
+    }
+}
+
+/// Copies `text` to the clipboard.
+pub fn copy(text: &str) -> Result<()> {
+    write(text, None)
+}
+
+/// Copies a link to the page; HTML is offered as `text/html` so it pastes as
+/// a link into rich text editors (xsel can only copy it as text).
+pub fn copy_link(title: &str, url: &str, format: Format) -> Result<()> {
+    let mime = (format == Format::Html).then_some("text/html");
+    write(&format.link(title, url), mime)
+}
+
+/// Asks for a format in rofi, then copies the link in it.
+pub fn choose_and_copy(title: &str, url: &str) -> Result<()> {
+    let labels: Vec<&str> = Format::ALL.iter().map(|f| f.label()).collect();
+    let choice = crate::rofi_choose("Copy as", &labels);
+    match Format::ALL.into_iter().find(|f| f.label() == choice) {
+        Some(format) => copy_link(title, url, format),
+        None => Ok(()),
+    }
+}
+
+/// The trimmed text in `selection`, empty when it holds none.
+pub fn paste(selection: Selection) -> Result<String> {
+    let (program, clipboard, primary) = tool(&READERS, |(program, _, _)| program)?;
+    let args = match selection {
+        Selection::Clipboard => clipboard,
+        Selection::Primary => primary,
+    };
+    let output = Command::new(program).args(*args).stderr(Stdio::null()).output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn write(text: &str, mime: Option<&str>) -> Result<()> {
+    let (program, args, type_flag) = tool(&WRITERS, |(program, _, _)| program)?;
+
+    let mut cmd = Command::new(program);
+    cmd.args(*args);
+    if let (Some(flag), Some(mime)) = (type_flag, mime) {
+        cmd.args([flag, mime]);
+    }
+
+    // The writers fork to keep serving the selection, so no pipes are kept
+    // open that would make us wait for that child.
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// The first tool found on `$PATH`; the wl-clipboard ones only under Wayland.
+fn tool<T>(tools: &[T], program: impl Fn(&T) -> &str) -> Result<&T> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    tools
+        .iter()
+        .filter(|t| wayland || !program(t).starts_with("wl-"))
+        .find(|t| on_path(program(t)))
+        .ok_or_else(|| anyhow!("No clipboard tool found; install wl-clipboard, xclip or xsel"))
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| Path::new(&dir).join(program).is_file()))
+}
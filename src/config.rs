@@ -1,4 +1,8 @@
+use anyhow::Result;
 use std::env;
+use std::fs;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct BrowserConfig {
@@ -7,13 +11,64 @@ pub struct BrowserConfig {
     pub history_path: String,
     pub window_class: String,
     pub cdp_port: u16,
+    /// Opening more bookmarks than this at once asks for confirmation first
+    /// (`$BRAVE_ROFI_CONFIRM_OVER`).
+    pub open_all_confirm: usize,
+    /// `--user-data-dir` when we launch the browser ourselves
+    /// (`$BRAVE_ROFI_USER_DATA_DIR`); `None` uses its default profile.
+    pub user_data_dir: Option<String>,
+    /// When an open tab counts as already showing a URL
+    /// (`$BRAVE_ROFI_URL_MATCH`).
+    pub url_match: UrlMatch,
+    /// Memory and CPU columns in the tab list (`$BRAVE_ROFI_SHOW_USAGE=1`);
+    /// sampling them makes the menu open a little later.
+    pub show_usage: bool,
+}
+
+/// How loosely URLs are compared when looking for a tab to switch to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UrlMatch {
+    /// Byte-for-byte equal.
+    Exact,
+    /// Ignoring the `#fragment`, a trailing slash and a `www.` prefix.
+    Page,
+    /// Like `Page`, also ignoring the query string and the scheme.
+    Path,
+}
+
+impl UrlMatch {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "exact" => Some(UrlMatch::Exact),
+            "page" => Some(UrlMatch::Page),
+            "path" => Some(UrlMatch::Path),
+            _ => None,
+        }
+    }
+
+    /// Whether a tab showing `a` counts as showing `b`.
+    pub fn same(self, a: &str, b: &str) -> bool {
+        a == b || self != UrlMatch::Exact && self.normalize(a) == self.normalize(b)
+    }
+
+    fn normalize(self, url: &str) -> String {
+        let Ok(mut url) = url::Url::parse(url) else { return url.to_string() };
+        url.set_fragment(None);
+        if self == UrlMatch::Path {
+            url.set_query(None);
+        }
+        let host = url.host_str().unwrap_or_default().trim_start_matches("www.").to_string();
+        let rest = &url[url::Position::BeforePort..];
+        let scheme = if self == UrlMatch::Path { "" } else { url.scheme() };
+        format!("{}://{}{}", scheme, host, rest.trim_end_matches('/'))
+    }
 }
 
 impl BrowserConfig {
     pub fn from_env() -> Self {
         let browser = env::var("BROWSER").unwrap_or_else(|_| "brave-beta".to_string());
         
-        match browser.as_str() {
+        let mut config = match browser.as_str() {
             "brave-beta" => Self::brave_beta(),
             "brave" => Self::brave(),
             "zen" => Self::zen(),
@@ -22,9 +77,55 @@ impl BrowserConfig {
                 eprintln!("Unknown browser '{}', using brave-beta", browser);
                 Self::brave_beta()
             }
+        };
+
+        if let Some(n) = env::var("BRAVE_ROFI_CONFIRM_OVER").ok().and_then(|v| v.parse().ok()) {
+            config.open_all_confirm = n;
+        }
+
+        if let Ok(value) = env::var("BRAVE_ROFI_URL_MATCH") {
+            match UrlMatch::parse(&value) {
+                Some(url_match) => config.url_match = url_match,
+                None => eprintln!("Unknown BRAVE_ROFI_URL_MATCH '{}', using page", value),
+            }
+        }
+
+        config.show_usage = env::var("BRAVE_ROFI_SHOW_USAGE").is_ok_and(|v| v == "1");
+
+        if let Ok(dir) = env::var("BRAVE_ROFI_USER_DATA_DIR") {
+            config.history_path = format!("{}/Default/History", dir);
+            config.user_data_dir = Some(dir);
+        }
+
+        config
+    }
+
+    /// The browser's user data directory, which holds `SingletonLock`.
+    pub fn data_dir(&self) -> PathBuf {
+        match &self.user_data_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(&self.history_path)
+                .ancestors()
+                .nth(2)
+                .map(PathBuf::from)
+                .unwrap_or_default(),
         }
     }
     
+    /// `$XDG_CACHE_HOME/brave-rofi/<browser>-<file>`, creating the directory
+    /// with owner-only permissions since it holds browsing data.
+    pub fn cache_path(&self, file: &str) -> Result<PathBuf> {
+        let base = match env::var_os("XDG_CACHE_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env::var("HOME")?).join(".cache"),
+        };
+        let dir = base.join("brave-rofi");
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+
+        let slug = self.name.to_lowercase().replace(' ', "-");
+        Ok(dir.join(format!("{}-{}", slug, file)))
+    }
+
     fn brave_beta() -> Self {
         let home = env::var("HOME").unwrap_or_else(|_| "/home/user".to_string());
         Self {
@@ -33,6 +134,10 @@ impl BrowserConfig {
             history_path: format!("{}/.config/BraveSoftware/Brave-Browser-Beta/Default/History", home),
             window_class: "Brave-browser".to_string(),
             cdp_port: 9222,
+            open_all_confirm: 10,
+            user_data_dir: None,
+            url_match: UrlMatch::Page,
+            show_usage: false,
         }
     }
     
@@ -44,6 +149,10 @@ impl BrowserConfig {
             history_path: format!("{}/.config/BraveSoftware/Brave-Browser/Default/History", home),
             window_class: "Brave-browser".to_string(),
             cdp_port: 9222,
+            open_all_confirm: 10,
+            user_data_dir: None,
+            url_match: UrlMatch::Page,
+            show_usage: false,
         }
     }
     
@@ -55,6 +164,10 @@ impl BrowserConfig {
             history_path: format!("{}/.zen/default/places.sqlite", home),
             window_class: "zen".to_string(),
             cdp_port: 9222,
+            open_all_confirm: 10,
+            user_data_dir: None,
+            url_match: UrlMatch::Page,
+            show_usage: false,
         }
     }
     
@@ -66,6 +179,50 @@ impl BrowserConfig {
             history_path: format!("{}/.config/chromium/Default/History", home),
             window_class: "Chromium".to_string(),
             cdp_port: 9222,
+            open_all_confirm: 10,
+            user_data_dir: None,
+            url_match: UrlMatch::Page,
+            show_usage: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_compares_bytes() {
+        assert!(UrlMatch::Exact.same("https://example.com/a", "https://example.com/a"));
+        assert!(!UrlMatch::Exact.same("https://example.com/a", "https://example.com/a/"));
+        assert!(!UrlMatch::Exact.same("https://example.com/a", "https://example.com/a#top"));
+    }
+
+    #[test]
+    fn page_ignores_trailing_slash_www_and_fragment() {
+        let page = UrlMatch::Page;
+        assert_eq!(page.normalize("https://www.example.com/a/#top"), "https://example.com/a");
+        assert!(page.same("https://example.com/a/", "https://example.com/a"));
+        assert!(page.same("https://example.com", "https://example.com/"));
+        assert!(page.same("https://www.example.com/a", "https://example.com/a"));
+        assert!(page.same("https://example.com/a#top", "https://example.com/a"));
+        assert!(!page.same("http://example.com/a", "https://example.com/a"));
+        assert!(!page.same("https://example.com/a?q=1", "https://example.com/a?q=2"));
+        assert!(!page.same("https://example.com/a", "https://example.com/b"));
+    }
+
+    #[test]
+    fn path_also_ignores_scheme_and_query() {
+        let path = UrlMatch::Path;
+        assert_eq!(path.normalize("http://www.example.com/a/?q=1#top"), "://example.com/a");
+        assert!(path.same("http://example.com/a", "https://example.com/a/"));
+        assert!(path.same("https://example.com/a?q=1", "https://example.com/a?q=2"));
+        assert!(!path.same("https://example.com:8080/a", "https://example.com/a"));
+    }
+
+    #[test]
+    fn unparsable_urls_compare_as_they_are() {
+        assert_eq!(UrlMatch::Page.normalize("not a url/"), "not a url/");
+        assert!(!UrlMatch::Page.same("not a url/", "not a url"));
+    }
+}
@@ -0,0 +1,168 @@
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdout, Command};
+use tokio::time::{sleep_until, timeout_at, Instant};
+use crate::config::BrowserConfig;
+use crate::{call_cdp, connect_cdp};
+
+/// How long after starting a `Watch` to wait for the browser to show a page
+/// before focusing it anyway.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Activates a tab, then focuses its window once the window title shows it.
+pub async fn tab(target_id: &str, title: &str, config: &BrowserConfig) -> Result<()> {
+    let mut watch = Watch::start(config).await;
+    crate::activate_tab(target_id, config).await?;
+
+    // A tab that was already its window's active one changes no title.
+    if !showing(title, config) {
+        let deadline = watch.deadline;
+        loop {
+            tokio::select! {
+                Some((window, name)) = watch.next() => if name.starts_with(title) {
+                    focus_window(window);
+                    return Ok(());
+                },
+                _ = sleep_until(deadline) => break,
+            }
+        }
+    }
+    crate::find_and_focus_browser_window(title, config);
+    Ok(())
+}
+
+/// Opens a tab with `Target.createTarget` `params` and focuses the window
+/// that shows it. `Target.targetInfoChanged` tells us the new page's title,
+/// so a window event for another window isn't mistaken for it.
+pub async fn open(params: Value, config: &BrowserConfig) -> Result<()> {
+    let mut watch = Watch::start(config).await;
+    let mut ws = connect_cdp(config).await?;
+    call_cdp(&mut ws, 1, "Target.setDiscoverTargets", json!({ "discover": true })).await?;
+    let created = call_cdp(&mut ws, 2, "Target.createTarget", params).await?;
+    let id = created["targetId"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Target.createTarget returned no target"))?
+        .to_string();
+
+    let deadline = watch.deadline;
+    let mut title: Option<String> = None;
+    let mut window = None;
+    loop {
+        tokio::select! {
+            Some(Ok(msg)) = ws.next() => {
+                let Ok(event) = serde_json::from_str::<Value>(msg.to_text().unwrap_or_default()) else { continue };
+                let info = &event["params"]["targetInfo"];
+                if event["method"] == "Target.targetInfoChanged" && info["targetId"] == id.as_str() {
+                    title = info["title"].as_str().filter(|t| !t.is_empty()).map(str::to_string);
+                    if title.as_deref().is_some_and(|t| showing(t, config)) {
+                        break;
+                    }
+                }
+            }
+            Some((id, name)) = watch.next() => {
+                if title.as_deref().is_none_or(|t| name.starts_with(t)) {
+                    window = Some(id);
+                    break;
+                }
+            }
+            _ = sleep_until(deadline) => break,
+        }
+    }
+
+    match (window, &title) {
+        (Some(window), _) => focus_window(window),
+        (None, Some(title)) => crate::find_and_focus_browser_window(title, config),
+        (None, None) => crate::focus_browser(config),
+    }
+    Ok(())
+}
+
+/// i3 window events for the browser, subscribed to before acting so that the
+/// browser's reaction can't be missed. Without i3 it never yields an event
+/// and callers fall back to their timeout.
+pub struct Watch {
+    events: Option<(Child, Lines<BufReader<ChildStdout>>)>,
+    config: BrowserConfig,
+    /// When to stop waiting, shared by the subscription and every wait after
+    /// it so that focusing never takes longer than `TIMEOUT` in all.
+    deadline: Instant,
+}
+
+impl Watch {
+    pub async fn start(config: &BrowserConfig) -> Self {
+        let deadline = Instant::now() + TIMEOUT;
+        let child = Command::new("i3-msg")
+            .args(["-t", "subscribe", "-m", r#"["window"]"#])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn();
+        let mut events = child.ok().and_then(|mut child| {
+            let lines = BufReader::new(child.stdout.take()?).lines();
+            Some((child, lines))
+        });
+
+        // i3 acknowledges the subscription before sending any event.
+        if let Some((_, lines)) = &mut events {
+            if !matches!(timeout_at(deadline, lines.next_line()).await, Ok(Ok(Some(_)))) {
+                events = None;
+            }
+        }
+        Self { events, config: config.clone(), deadline }
+    }
+
+    /// Waits for the next browser window to open, retitle or take focus, as
+    /// `(window id, title)`.
+    pub async fn next(&mut self) -> Option<(u64, String)> {
+        if let Some((_, lines)) = &mut self.events {
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Ok(event) = serde_json::from_str::<Value>(&line) else { continue };
+                let container = &event["container"];
+                let (Some(window), Some(name)) = (container["window"].as_u64(), container["name"].as_str()) else {
+                    continue;
+                };
+                let browser = container["window_properties"]["class"] == self.config.window_class.as_str()
+                    || name.contains(&self.config.name);
+                if browser && matches!(event["change"].as_str(), Some("new" | "title" | "focus")) {
+                    return Some((window, name.to_string()));
+                }
+            }
+            self.events = None;
+        }
+        std::future::pending().await
+    }
+
+    /// After opening pages without knowing their titles (incognito, several
+    /// at once, a fresh browser process): focuses the first browser window
+    /// to change, or any browser window on timeout.
+    pub async fn focus_next(mut self) {
+        match timeout_at(self.deadline, self.next()).await {
+            Ok(Some((window, _))) => focus_window(window),
+            _ => crate::focus_browser(&self.config),
+        }
+    }
+}
+
+/// Whether a browser window's title already shows `title`.
+fn showing(title: &str, config: &BrowserConfig) -> bool {
+    std::process::Command::new("i3-msg")
+        .args(["-t", "get_tree"])
+        .output()
+        .ok()
+        .and_then(|output| serde_json::from_slice::<Value>(&output.stdout).ok())
+        .is_some_and(|tree| {
+            crate::find_browser_windows(&tree, config)
+                .iter()
+                .any(|(_, name)| name.starts_with(title))
+        })
+}
+
+fn focus_window(window: u64) {
+    let _ = std::process::Command::new("i3-msg")
+        .arg(format!("[id=\"{}\"] focus", window))
+        .output();
+}
@@ -0,0 +1,380 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::BrowserConfig;
+use crate::Tab;
+
+// Session command ids from Chromium's session_service_commands.cc.
+const SET_TAB_WINDOW: u8 = 0;
+const UPDATE_TAB_NAVIGATION: u8 = 6;
+const SET_SELECTED_NAVIGATION_INDEX: u8 = 7;
+const TAB_CLOSED: u8 = 16;
+const WINDOW_CLOSED: u8 = 17;
+const LAST_ACTIVE_TIME: u8 = 21;
+const SET_TAB_GROUP: u8 = 25;
+const SET_TAB_GROUP_METADATA2: u8 = 27;
+
+/// `tab_groups::TabGroupColorId`, in enum order.
+const COLORS: [&str; 9] = ["grey", "blue", "red", "yellow", "green", "pink", "purple", "cyan", "orange"];
+
+/// What the session file says about open tabs and their groups.
+struct Session {
+    tabs: HashMap<i32, SessionTab>,
+    /// Group token to menu label.
+    groups: HashMap<(u64, u64), String>,
+}
+
+#[derive(Default)]
+struct SessionTab {
+    window: i32,
+    navigations: HashMap<i32, String>,
+    selected: i32,
+    group: Option<(u64, u64)>,
+    /// Unix seconds when the tab was last the active one.
+    last_active: Option<i64>,
+}
+
+/// Sets `tab.group` from the browser's session file. CDP doesn't expose tab
+/// groups, so session tabs are matched to CDP targets by their current URL;
+/// the file is written every few seconds and may lag behind just-opened tabs.
+pub fn assign(tabs: &mut [Tab], config: &BrowserConfig) -> Result<()> {
+    let data = fs::read(latest_session(config)?)?;
+    let session = parse(&data)?;
+
+    let mut unclaimed: Vec<(&str, String)> = session
+        .tabs
+        .values()
+        .filter_map(|t| {
+            let group = session.groups.get(&t.group?)?;
+            Some((t.navigations.get(&t.selected)?.as_str(), group.clone()))
+        })
+        .collect();
+
+    for tab in tabs.iter_mut().filter(|t| !t.incognito) {
+        if let Some(i) = unclaimed.iter().position(|(url, _)| *url == tab.url) {
+            tab.group = Some(unclaimed.swap_remove(i).1);
+        }
+    }
+    Ok(())
+}
+
+/// When each tab was last active, in Unix seconds, matched by URL like
+/// `assign`; `None` for incognito tabs and tabs the session file lacks.
+pub fn last_active(tabs: &[Tab], config: &BrowserConfig) -> Result<Vec<Option<i64>>> {
+    let data = fs::read(latest_session(config)?)?;
+    let session = parse(&data)?;
+
+    let mut unclaimed: Vec<(&str, i64)> = session
+        .tabs
+        .values()
+        .filter_map(|t| Some((t.navigations.get(&t.selected)?.as_str(), t.last_active?)))
+        .collect();
+
+    Ok(tabs
+        .iter()
+        .map(|tab| {
+            let i = unclaimed.iter().position(|(url, _)| *url == tab.url).filter(|_| !tab.incognito)?;
+            Some(unclaimed.swap_remove(i).1)
+        })
+        .collect())
+}
+
+/// Pick a tab group, then list its tabs, close it, or save it as bookmarks.
+pub async fn group_menu(tabs: &[Tab], config: &BrowserConfig) -> Result<()> {
+    let mut labels: Vec<&str> = Vec::new();
+    for label in tabs.iter().filter_map(|t| t.group.as_deref()) {
+        if !labels.contains(&label) {
+            labels.push(label);
+        }
+    }
+    let group = crate::rofi_choose("Tab group", &labels);
+    let members: Vec<&Tab> = tabs.iter().filter(|t| t.group.as_deref() == Some(&group)).collect();
+    if members.is_empty() {
+        return Ok(());
+    }
+
+    match crate::rofi_choose(&group, &["Show tabs", "Close group", "Save as bookmarks"]).as_str() {
+        "Show tabs" => {
+            let lines: Vec<String> = members
+                .iter()
+                .enumerate()
+                .map(|(i, t)| format!("{}. {} - {}", i + 1, t.title, t.url))
+                .collect();
+            let choice = crate::rofi_choose(&group, &lines.iter().map(String::as_str).collect::<Vec<_>>());
+            if let Some(tab) = lines.iter().position(|l| *l == choice).map(|i| members[i]) {
+                crate::focus::tab(&tab.target_id, &tab.title, config).await?;
+            }
+        }
+        "Close group" if crate::rofi_confirm(&format!("Close {} tabs?", members.len())) == "YES" => {
+            for tab in members {
+                let _ = crate::close_tab(&tab.target_id, config).await;
+            }
+        }
+        "Save as bookmarks" => {
+            let pages: Vec<(String, String)> = members.iter().map(|t| (t.title.clone(), t.url.clone())).collect();
+            let title = group.split(" · ").next().unwrap_or(&group);
+            let (tag, saved) = crate::bookmarks::save_tabs(title, &pages)?;
+            crate::rofi_message(&format!(
+                "Saved {} tabs as #{}; reopen them with Open folder… or bbr bookmarks open-all '#{}'",
+                saved, tag, tag
+            ));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The newest `Sessions/Session_*`, or the pre-M86 `Current Session`.
+fn latest_session(config: &BrowserConfig) -> Result<PathBuf> {
+    let profile = Path::new(&config.history_path)
+        .parent()
+        .ok_or_else(|| anyhow!("No profile directory"))?;
+
+    let newest = fs::read_dir(profile.join("Sessions"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with("Session_"))
+        .max_by_key(|e| e.metadata().and_then(|m| m.modified()).ok())
+        .map(|e| e.path());
+
+    newest
+        .or_else(|| Some(profile.join("Current Session")).filter(|p| p.exists()))
+        .ok_or_else(|| anyhow!("No session file in {}", profile.display()))
+}
+
+/// Replays the SNSS command log: a `SNSS` header and version, then commands
+/// of `u16 size, u8 id, payload`. A command or pickle cut short is an error.
+fn parse(data: &[u8]) -> Result<Session> {
+    if data.get(..4) != Some(b"SNSS") {
+        return Err(anyhow!("Not a session file"));
+    }
+
+    let mut tabs: HashMap<i32, SessionTab> = HashMap::new();
+    let mut groups = HashMap::new();
+    let mut pos = 8;
+    while let Some(size) = data.get(pos..pos + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize) {
+        let command = data
+            .get(pos + 2..pos + 2 + size)
+            .ok_or_else(|| anyhow!("Session command at byte {} is cut short", pos))?;
+        pos += 2 + size;
+        let Some((&id, payload)) = command.split_first() else { continue };
+
+        match id {
+            SET_TAB_WINDOW => {
+                if let (Some(window), Some(tab)) = (i32_at(payload, 0), i32_at(payload, 4)) {
+                    tabs.entry(tab).or_default().window = window;
+                }
+            }
+            UPDATE_TAB_NAVIGATION => {
+                let mut pickle = Pickle::new(payload)?;
+                let (tab, index, url) = (pickle.i32()?, pickle.i32()?, pickle.string()?);
+                tabs.entry(tab).or_default().navigations.insert(index, url);
+            }
+            SET_SELECTED_NAVIGATION_INDEX => {
+                if let (Some(tab), Some(index)) = (i32_at(payload, 0), i32_at(payload, 4)) {
+                    tabs.entry(tab).or_default().selected = index;
+                }
+            }
+            TAB_CLOSED => {
+                if let Some(tab) = i32_at(payload, 0) {
+                    tabs.remove(&tab);
+                }
+            }
+            WINDOW_CLOSED => {
+                if let Some(window) = i32_at(payload, 0) {
+                    tabs.retain(|_, t| t.window != window);
+                }
+            }
+            SET_TAB_GROUP => {
+                // struct { i32 tab; u64 high; u64 low; bool has_group }, 8-byte aligned.
+                if let (Some(tab), Some(high), Some(low), Some(&has_group)) =
+                    (i32_at(payload, 0), u64_at(payload, 8), u64_at(payload, 16), payload.get(24))
+                {
+                    tabs.entry(tab).or_default().group = (has_group != 0).then_some((high, low));
+                }
+            }
+            LAST_ACTIVE_TIME => {
+                // struct { i32 tab; i64 microseconds since 1601 }, 8-byte aligned.
+                if let (Some(tab), Some(time)) = (i32_at(payload, 0), u64_at(payload, 8)) {
+                    tabs.entry(tab).or_default().last_active = Some(crate::chrome_time::to_unix(time as i64));
+                }
+            }
+            SET_TAB_GROUP_METADATA2 => {
+                let mut pickle = Pickle::new(payload)?;
+                let (high, low, title, color) = (pickle.u64()?, pickle.u64()?, pickle.string16()?, pickle.i32()?);
+                groups.insert((high, low), label(&title, color));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Session { tabs, groups })
+}
+
+/// `Work · blue`, or just the colour for an unnamed group.
+fn label(title: &str, color: i32) -> String {
+    let color = usize::try_from(color).ok().and_then(|c| COLORS.get(c)).unwrap_or(&"grey");
+    if title.is_empty() {
+        format!("{} group", color)
+    } else {
+        format!("{} · {}", title, color)
+    }
+}
+
+fn i32_at(data: &[u8], at: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Reader for `base::Pickle`: a `u32` payload size, then 4-byte aligned fields.
+struct Pickle<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Pickle<'a> {
+    fn new(command: &'a [u8]) -> Result<Self> {
+        let size = command
+            .get(..4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(|| anyhow!("Session pickle has no header"))?;
+        let data = command
+            .get(..4 + size)
+            .ok_or_else(|| anyhow!("Session pickle of {} bytes overruns its command", size))?;
+        Ok(Self { data, pos: 4 })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or_else(|| anyhow!("Session pickle field of {} bytes overruns the pickle", len))?;
+        self.pos += len.next_multiple_of(4);
+        Ok(bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = usize::try_from(self.i32()?)?;
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn string16(&mut self) -> Result<String> {
+        let len = usize::try_from(self.i32()?)?;
+        let units: Vec<u16> = self
+            .take(len * 2)?
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        Ok(String::from_utf16_lossy(&units))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pickle: `u32` payload size, then the fields padded to 4 bytes.
+    fn pickle(fields: &[&[u8]]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for field in fields {
+            payload.extend_from_slice(field);
+            payload.resize(payload.len().next_multiple_of(4), 0);
+        }
+        [(payload.len() as u32).to_le_bytes().to_vec(), payload].concat()
+    }
+
+    fn string(s: &str) -> Vec<u8> {
+        [(s.len() as i32).to_le_bytes().to_vec(), s.as_bytes().to_vec()].concat()
+    }
+
+    fn string16(s: &str) -> Vec<u8> {
+        let units: Vec<u8> = s.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        [(s.encode_utf16().count() as i32).to_le_bytes().to_vec(), units].concat()
+    }
+
+    fn command(id: u8, payload: &[u8]) -> Vec<u8> {
+        [((payload.len() + 1) as u16).to_le_bytes().to_vec(), vec![id], payload.to_vec()].concat()
+    }
+
+    fn session(commands: &[Vec<u8>]) -> Vec<u8> {
+        [b"SNSS".to_vec(), 3i32.to_le_bytes().to_vec(), commands.concat()].concat()
+    }
+
+    /// Tab 1 in window 7, showing `url` and grouped in a blue "Work" group.
+    fn grouped_tab(url: &str) -> Vec<Vec<u8>> {
+        let group = [0u64.to_le_bytes(), 5u64.to_le_bytes()].concat();
+        let mut set_group = [1i32.to_le_bytes().to_vec(), vec![0; 4], group.clone()].concat();
+        set_group.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        let last_active = [1i32.to_le_bytes().to_vec(), vec![0; 4], 13_348_540_800_000_000u64.to_le_bytes().to_vec()];
+        vec![
+            command(SET_TAB_WINDOW, &[7i32.to_le_bytes(), 1i32.to_le_bytes()].concat()),
+            command(UPDATE_TAB_NAVIGATION, &pickle(&[&1i32.to_le_bytes(), &0i32.to_le_bytes(), &string(url)])),
+            command(SET_SELECTED_NAVIGATION_INDEX, &[1i32.to_le_bytes(), 0i32.to_le_bytes()].concat()),
+            command(SET_TAB_GROUP, &set_group),
+            command(LAST_ACTIVE_TIME, &last_active.concat()),
+            command(
+                SET_TAB_GROUP_METADATA2,
+                &pickle(&[&group[..8], &group[8..], &string16("Work"), &1i32.to_le_bytes()]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn parses_a_minimal_session() {
+        let session = parse(&session(&grouped_tab("https://example.com/"))).unwrap();
+        let tab = &session.tabs[&1];
+        assert_eq!(tab.window, 7);
+        assert_eq!(tab.navigations[&tab.selected], "https://example.com/");
+        assert_eq!(tab.last_active, Some(1_704_067_200));
+        assert_eq!(session.groups[&tab.group.unwrap()], "Work · blue");
+    }
+
+    #[test]
+    fn closed_tabs_are_dropped() {
+        let mut commands = grouped_tab("https://example.com/");
+        commands.push(command(TAB_CLOSED, &1i32.to_le_bytes()));
+        assert!(parse(&session(&commands)).unwrap().tabs.is_empty());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse(b"").is_err());
+        assert!(parse(b"{\"roots\": {}}").is_err());
+    }
+
+    #[test]
+    fn rejects_a_truncated_command() {
+        let mut data = session(&grouped_tab("https://example.com/"));
+        data.truncate(data.len() - 3);
+        assert!(parse(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_a_pickle_longer_than_its_command() {
+        let mut navigation = pickle(&[&1i32.to_le_bytes(), &0i32.to_le_bytes(), &string("https://example.com/")]);
+        navigation[..4].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(parse(&session(&[command(UPDATE_TAB_NAVIGATION, &navigation)])).is_err());
+    }
+
+    #[test]
+    fn rejects_a_string_longer_than_its_pickle() {
+        let navigation = pickle(&[&1i32.to_le_bytes(), &0i32.to_le_bytes(), &i32::MAX.to_le_bytes()]);
+        assert!(parse(&session(&[command(UPDATE_TAB_NAVIGATION, &navigation)])).is_err());
+        let metadata = pickle(&[&0u64.to_le_bytes(), &5u64.to_le_bytes(), &i32::MAX.to_le_bytes()]);
+        assert!(parse(&session(&[command(SET_TAB_GROUP_METADATA2, &metadata)])).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Deref;
+use std::os::unix::fs::OpenOptionsExt;
+use super::snapshot::{self, Snapshot};
+use crate::config::BrowserConfig;
+
+/// Mirrors the subset of Chromium's `urls`/`visits` schema the menus query, so
+/// the same SQL runs against either, plus an FTS5 table over titles and URLs.
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS meta (
+    key   TEXT PRIMARY KEY,
+    value INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS urls (
+    id              INTEGER PRIMARY KEY,
+    url             TEXT NOT NULL,
+    title           TEXT,
+    visit_count     INTEGER NOT NULL DEFAULT 0,
+    last_visit_time INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS urls_last_visit ON urls (last_visit_time);
+CREATE TABLE IF NOT EXISTS visits (
+    id         INTEGER PRIMARY KEY,
+    url        INTEGER NOT NULL,
+    visit_time INTEGER NOT NULL,
+    transition INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS visits_time ON visits (visit_time);
+CREATE INDEX IF NOT EXISTS visits_url ON visits (url);
+CREATE VIRTUAL TABLE IF NOT EXISTS urls_fts USING fts5 (
+    title, url, content = 'urls', content_rowid = 'id'
+);
+CREATE TRIGGER IF NOT EXISTS urls_ai AFTER INSERT ON urls BEGIN
+    INSERT INTO urls_fts (rowid, title, url) VALUES (new.id, new.title, new.url);
+END;
+CREATE TRIGGER IF NOT EXISTS urls_ad AFTER DELETE ON urls BEGIN
+    INSERT INTO urls_fts (urls_fts, rowid, title, url) VALUES ('delete', old.id, old.title, old.url);
+END;
+CREATE TRIGGER IF NOT EXISTS urls_au AFTER UPDATE ON urls BEGIN
+    INSERT INTO urls_fts (urls_fts, rowid, title, url) VALUES ('delete', old.id, old.title, old.url);
+    INSERT INTO urls_fts (rowid, title, url) VALUES (new.id, new.title, new.url);
+END;
+"#;
+
+/// A persistent copy of the browser history kept under the user's cache dir.
+///
+/// Each run only pulls visits with a higher `id` and URLs visited since the
+/// last sync, so the menu never has to re-read the whole profile, and skips
+/// even that while the profile's files are unchanged.
+pub struct Index {
+    conn: Connection,
+}
+
+impl Index {
+    pub fn open(config: &BrowserConfig) -> Result<Self> {
+        let path = config.cache_path("history.sqlite")?;
+
+        // Create the file ourselves so history never lands world-readable.
+        fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(0o600)
+            .open(&path)?;
+
+        let conn = Connection::open(&path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Syncs from a snapshot of the profile's History DB, unless neither it nor
+    /// its WAL has changed since the last sync.
+    pub fn refresh(&mut self, config: &BrowserConfig) -> Result<()> {
+        let stamp = snapshot::stamp(config);
+        if meta(&self.conn, "source_stamp")? == stamp {
+            return Ok(());
+        }
+        self.sync(&*Snapshot::open(config)?)?;
+        set_meta(&self.conn, "source_stamp", stamp)
+    }
+
+    /// Brings the index up to date with `source`, a Chromium History DB.
+    pub fn sync(&mut self, source: &Connection) -> Result<()> {
+        let source_max_visit: i64 = source
+            .query_row("SELECT COALESCE(MAX(id), 0) FROM visits", [], |row| row.get(0))?;
+
+        let tx = self.conn.transaction()?;
+
+        let mut max_visit = meta(&tx, "max_visit_id")?;
+        let mut last_visit = meta(&tx, "last_visit_time")?;
+
+        // History was cleared or rewritten behind our back: start over.
+        if source_max_visit < max_visit {
+            tx.execute_batch("DELETE FROM visits; DELETE FROM urls; DELETE FROM meta;")?;
+            max_visit = 0;
+            last_visit = 0;
+        }
+
+        {
+            let mut read = source.prepare(
+                "SELECT id, url, title, visit_count, last_visit_time
+                 FROM urls WHERE last_visit_time >= ?1",
+            )?;
+            let mut write = tx.prepare(
+                "INSERT INTO urls (id, url, title, visit_count, last_visit_time)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (id) DO UPDATE SET
+                     url = excluded.url,
+                     title = excluded.title,
+                     visit_count = excluded.visit_count,
+                     last_visit_time = excluded.last_visit_time",
+            )?;
+            let mut rows = read.query(params![last_visit])?;
+            while let Some(row) = rows.next()? {
+                let time: i64 = row.get(4)?;
+                last_visit = last_visit.max(time);
+                write.execute(params![
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)?,
+                    time,
+                ])?;
+            }
+        }
+
+        {
+            let mut read = source.prepare(
+                "SELECT id, url, visit_time, transition FROM visits WHERE id > ?1",
+            )?;
+            let mut write = tx.prepare(
+                "INSERT OR REPLACE INTO visits (id, url, visit_time, transition)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            let mut rows = read.query(params![max_visit])?;
+            while let Some(row) = rows.next()? {
+                write.execute(params![
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ])?;
+            }
+        }
+
+        // Everything in `source` is now indexed, so any surplus rows were
+        // deleted there (by the browser's own "clear history", say).
+        for table in ["urls", "visits"] {
+            let count = format!("SELECT COUNT(*) FROM {}", table);
+            let theirs: i64 = source.query_row(&count, [], |row| row.get(0))?;
+            let ours: i64 = tx.query_row(&count, [], |row| row.get(0))?;
+            if ours > theirs {
+                prune(source, &tx, table)?;
+            }
+        }
+
+        set_meta(&tx, "max_visit_id", source_max_visit)?;
+        set_meta(&tx, "last_visit_time", last_visit)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Mirrors a deletion of `urls` rows made in the live History DB.
+    pub fn remove(&mut self, ids: &[i64]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut visits = tx.prepare("DELETE FROM visits WHERE url = ?1")?;
+            let mut urls = tx.prepare("DELETE FROM urls WHERE id = ?1")?;
+            for id in ids {
+                visits.execute(params![id])?;
+                urls.execute(params![id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+impl Deref for Index {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+/// Turns free text into an FTS5 query matching every word as a prefix.
+pub fn fts_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Drops rows of `table` whose `id` no longer exists in `source`.
+fn prune(source: &Connection, index: &Connection, table: &str) -> Result<()> {
+    let select = format!("SELECT id FROM {}", table);
+    let mut live = HashSet::new();
+    let mut stmt = source.prepare(&select)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        live.insert(row.get::<_, i64>(0)?);
+    }
+
+    let mut stale = Vec::new();
+    let mut stmt = index.prepare(&select)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        if !live.contains(&id) {
+            stale.push(id);
+        }
+    }
+
+    let mut delete = index.prepare(&format!("DELETE FROM {} WHERE id = ?1", table))?;
+    for id in stale {
+        delete.execute(params![id])?;
+    }
+    Ok(())
+}
+
+fn meta(conn: &Connection, key: &str) -> Result<i64> {
+    Ok(conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()?
+        .unwrap_or(0))
+}
+
+fn set_meta(conn: &Connection, key: &str, value: i64) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )?;
+    Ok(())
+}
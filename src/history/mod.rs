@@ -0,0 +1,458 @@
+mod index;
+mod snapshot;
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OpenFlags};
+use std::collections::HashMap;
+use crate::config::BrowserConfig;
+use crate::rofi::Dmenu;
+
+pub use index::{fts_query, Index};
+
+/// Chromium's `PAGE_TRANSITION_TYPED` core transition type.
+const TRANSITION_TYPED: i64 = 1;
+/// Mask selecting the core transition type out of `visits.transition`.
+const TRANSITION_CORE_MASK: i64 = 0xFF;
+
+/// Duration units accepted by `Since::parse`, in seconds.
+const UNITS: [(char, i64); 5] = [('s', 1), ('m', 60), ('h', 3600), ('d', 86_400), ('w', 604_800)];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Since {
+    /// A relative window of `secs` ending now, typed in `unit`.
+    Ago { secs: i64, unit: char },
+    /// Since local midnight.
+    Today,
+    /// Since local midnight on Monday.
+    ThisWeek,
+}
+
+impl Since {
+    /// Parses durations such as `90s`, `30m`, `2h`, `2d` or `1w`.
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "today" => return Ok(Since::Today),
+            "week" => return Ok(Since::ThisWeek),
+            _ => {}
+        }
+
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (num, unit) = s.split_at(split);
+        let n: i64 = num.parse().map_err(|_| anyhow!("Invalid duration '{}'", s))?;
+        let (unit, unit_secs) = UNITS
+            .into_iter()
+            .find(|(u, _)| unit.len() == 1 && unit.starts_with(*u))
+            .ok_or_else(|| anyhow!("Invalid duration unit in '{}'", s))?;
+        let secs = n.checked_mul(unit_secs).ok_or_else(|| anyhow!("Duration '{}' is too long", s))?;
+        Ok(Since::Ago { secs, unit })
+    }
+
+    /// SQLite date modifiers applied to `'now'` to obtain the cutoff.
+    fn modifiers(&self) -> String {
+        match self {
+            Since::Ago { secs, .. } => format!("'-{} seconds'", secs),
+            Since::Today => "'localtime', 'start of day', 'utc'".to_string(),
+            Since::ThisWeek => {
+                "'localtime', 'start of day', '-6 days', 'weekday 1', 'utc'".to_string()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub since: Option<Since>,
+    pub domain: Option<String>,
+    pub typed_only: bool,
+    /// Words matched against titles and URLs through the index's FTS table.
+    pub query: Option<String>,
+}
+
+impl HistoryFilter {
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        match self.since {
+            Some(Since::Today) => parts.push("today".to_string()),
+            Some(Since::ThisWeek) => parts.push("this week".to_string()),
+            Some(Since::Ago { secs, unit }) => {
+                let unit_secs = UNITS.iter().find(|(u, _)| *u == unit).map_or(1, |(_, s)| *s);
+                parts.push(format!("last {}{}", secs / unit_secs, unit));
+            }
+            None => {}
+        }
+        if let Some(domain) = &self.domain {
+            parts.push(domain.clone());
+        }
+        if self.typed_only {
+            parts.push("typed".to_string());
+        }
+        if let Some(query) = &self.query {
+            parts.push(format!("\"{}\"", query));
+        }
+        parts.join(", ")
+    }
+}
+
+/// rofi exit code for `-kb-custom-1`.
+const KB_DELETE: i32 = 10;
+/// rofi exit code for `-kb-custom-2`.
+const KB_DELETE_DOMAIN: i32 = 11;
+/// rofi exit code for `-kb-custom-3`.
+const KB_COPY: i32 = 12;
+/// rofi exit code for `-kb-custom-4`: open in a new tab even if one already shows it.
+const KB_NEW_TAB: i32 = 13;
+/// rofi exit code for `-kb-custom-5`.
+const KB_INCOGNITO: i32 = 14;
+
+pub fn show_history(config: &BrowserConfig, mut filter: HistoryFilter) -> Result<()> {
+    let mut index = Index::open(config)?;
+    index.refresh(config)?;
+
+    loop {
+        let mut menu = String::new();
+        menu.push_str("- All\n");
+        menu.push_str("- Today\n");
+        menu.push_str("- This week\n");
+        menu.push_str("- Typed only\n");
+        menu.push_str("- By domain…\n");
+        menu.push_str("- Search…\n");
+        menu.push_str("────\n");
+        menu.push_str(&format_entries(&index, &filter)?);
+
+        let prompt = match filter.describe() {
+            d if d.is_empty() => format!("{} History", config.name),
+            d => format!("{} History ({})", config.name, d),
+        };
+        let (code, selection) = Dmenu {
+            mesg: Some("Alt+1: delete entry · Alt+2: delete all visits to domain · Alt+3: copy link · Alt+Enter: new tab · Alt+i: incognito"),
+            keys: &["Alt+1", "Alt+2", "Alt+3", "Alt+Return", "Alt+i"],
+            ..dmenu(&prompt)
+        }
+        .run(&menu)?;
+
+        if code == KB_COPY {
+            if let Some(idx) = selection.find("http") {
+                let url = &selection[idx..];
+                // The menu truncates titles, so look the full one up.
+                let title: String = index
+                    .query_row("SELECT title FROM urls WHERE url = ?1", params![url], |row| row.get(0))
+                    .unwrap_or_default();
+                crate::clipboard::choose_and_copy(&title, url)?;
+            }
+            return Ok(());
+        }
+
+        if code == KB_DELETE || code == KB_DELETE_DOMAIN {
+            if let Some(idx) = selection.find("http") {
+                let url = &selection[idx..];
+                if code == KB_DELETE {
+                    delete_url(url, &mut index, config)?;
+                } else {
+                    delete_domain(url, &mut index, config)?;
+                }
+            }
+            continue;
+        }
+
+        match selection.as_str() {
+            "" => return Ok(()),
+            "- All" => filter = HistoryFilter::default(),
+            "- Today" => filter.since = Some(Since::Today),
+            "- This week" => filter.since = Some(Since::ThisWeek),
+            "- Typed only" => filter.typed_only = !filter.typed_only,
+            "- By domain…" => {
+                let domains = top_domains(&index, &filter)?.join("\n");
+                let (_, domain) = dmenu("Domain").run(&domains)?;
+                if !domain.is_empty() {
+                    filter.domain = Some(domain);
+                }
+            }
+            "- Search…" => {
+                let query = crate::search::prompt("Search history");
+                filter.query = (!query.is_empty()).then_some(query);
+            }
+            _ => {
+                if let Some(idx) = selection.find("http") {
+                    if let Err(e) = open_url(&selection[idx..], code, config) {
+                        crate::rofi_message(&format!("Could not open {}: {}", &selection[idx..], e));
+                    }
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn cutoff(conn: &Connection, since: Since) -> Result<i64> {
+    let sql = format!(
+        "SELECT CAST(strftime('%s', 'now', {}) AS INTEGER)",
+        since.modifiers()
+    );
+    // NULL past SQLite's date range, where the window covers all history.
+    let unix: Option<i64> = conn.query_row(&sql, [], |row| row.get(0))?;
+    Ok(unix.map_or(0, crate::chrome_time::from_unix))
+}
+
+fn format_entries(conn: &Connection, filter: &HistoryFilter) -> Result<String> {
+    let cols: usize = 40;
+
+    let since = match filter.since {
+        Some(since) => cutoff(conn, since)?,
+        None => 0,
+    };
+    // `LIKE` only narrows the candidates; `on_domain` decides.
+    let pattern = match &filter.domain {
+        Some(domain) => format!("%{}%", domain),
+        None => "%".to_string(),
+    };
+    let transition = if filter.typed_only { TRANSITION_TYPED } else { -1 };
+    let query = filter.query.as_deref().map(index::fts_query);
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT u.title, u.url, MAX(v.visit_time) AS last_visit
+        FROM visits v
+        JOIN urls u ON u.id = v.url
+        WHERE u.title IS NOT NULL AND u.title != ''
+          AND v.visit_time >= ?1
+          AND u.url LIKE ?2
+          AND (?3 = -1 OR (v.transition & ?4) = ?3)
+          AND (?5 IS NULL OR u.id IN (SELECT rowid FROM urls_fts WHERE urls_fts MATCH ?5))
+        GROUP BY u.id
+        ORDER BY last_visit DESC
+        LIMIT 100000
+        "#,
+    )?;
+
+    let rows = stmt.query_map(
+        params![since, pattern, transition, TRANSITION_CORE_MASK, query],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+    )?;
+
+    let mut menu = String::new();
+    for row in rows {
+        let (title, url) = row?;
+        if filter.domain.as_ref().is_some_and(|domain| !on_domain(&url, domain)) {
+            continue;
+        }
+        let truncated = title.chars().take(cols).collect::<String>();
+        menu.push_str(&format!("{:<width$}  {}", truncated, url, width = cols));
+        menu.push('\n');
+    }
+    Ok(menu)
+}
+
+/// Hosts ordered by total visit count, restricted to the active time filter.
+fn top_domains(conn: &Connection, filter: &HistoryFilter) -> Result<Vec<String>> {
+    let since = match filter.since {
+        Some(since) => cutoff(conn, since)?,
+        None => 0,
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT url, visit_count FROM urls WHERE last_visit_time >= ?1",
+    )?;
+    let rows = stmt.query_map(params![since], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    let mut counts: HashMap<String, i64> = HashMap::new();
+    for row in rows {
+        let (url, visits) = row?;
+        if let Some(host) = url::Url::parse(&url).ok().and_then(|u| u.host_str().map(str::to_string)) {
+            *counts.entry(host).or_default() += visits;
+        }
+    }
+
+    let mut domains: Vec<(String, i64)> = counts.into_iter().collect();
+    domains.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(domains.into_iter().map(|(host, _)| host).collect())
+}
+
+/// Removes a single URL and all of its visits from the live History DB.
+fn delete_url(url: &str, index: &mut Index, config: &BrowserConfig) -> Result<()> {
+    if crate::rofi_confirm(&format!("Delete {}?", url)) != "YES" {
+        return Ok(());
+    }
+    delete_where("url = ?1", url, |_| true, index, config)
+}
+
+/// Removes every URL on the selected entry's host (and its subdomains).
+fn delete_domain(url: &str, index: &mut Index, config: &BrowserConfig) -> Result<()> {
+    let host = url::Url::parse(url)?
+        .host_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("No host in '{}'", url))?;
+
+    if crate::rofi_confirm(&format!("Delete all visits to {}?", host)) != "YES" {
+        return Ok(());
+    }
+    // `LIKE` only narrows the candidates; the host check decides.
+    let pattern = format!("%{}%", host);
+    delete_where("url LIKE ?1", &pattern, |url| on_domain(url, &host), index, config)
+}
+
+/// Whether `url`'s host is `domain` or one of its subdomains.
+fn on_domain(url: &str, domain: &str) -> bool {
+    url::Url::parse(url).ok().is_some_and(|u| {
+        u.host_str()
+            .is_some_and(|host| host == domain || host.ends_with(&format!(".{}", domain)))
+    })
+}
+
+/// Rows keyed on a `urls.id` (`?1`) or on its visits, deleted before the URL
+/// itself. Tables missing from older profiles are skipped.
+const RELATED: &[(&str, &str)] = &[
+    ("visit_source", "DELETE FROM visit_source WHERE id IN (SELECT id FROM visits WHERE url = ?1)"),
+    ("content_annotations", "DELETE FROM content_annotations WHERE visit_id IN (SELECT id FROM visits WHERE url = ?1)"),
+    ("context_annotations", "DELETE FROM context_annotations WHERE visit_id IN (SELECT id FROM visits WHERE url = ?1)"),
+    ("segment_usage", "DELETE FROM segment_usage WHERE segment_id IN (SELECT id FROM segments WHERE url_id = ?1)"),
+    ("segments", "DELETE FROM segments WHERE url_id = ?1"),
+    ("keyword_search_terms", "DELETE FROM keyword_search_terms WHERE url_id = ?1"),
+    ("visits", "DELETE FROM visits WHERE url = ?1"),
+];
+
+/// Deletes the `urls` rows matching `condition` (with `arg` as `?1`) whose URL
+/// passes `keep`, together with their visits, search terms, segments and
+/// annotations, then drops the same rows from the local index.
+///
+/// The browser keeps an exclusive lock on History while running, so this only
+/// succeeds once it has been closed; otherwise the user is told to close it.
+fn delete_where(
+    condition: &str,
+    arg: &str,
+    keep: impl Fn(&str) -> bool,
+    index: &mut Index,
+    config: &BrowserConfig,
+) -> Result<()> {
+    let mut conn = Connection::open_with_flags(
+        &config.history_path,
+        OpenFlags::SQLITE_OPEN_READ_WRITE,
+    )?;
+    conn.busy_timeout(std::time::Duration::from_millis(500))?;
+
+    let tx = match conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate) {
+        Ok(tx) => tx,
+        Err(_) => {
+            crate::rofi_message(&format!("Close {} to delete history entries", config.name));
+            return Ok(());
+        }
+    };
+
+    let ids = {
+        let mut stmt = tx.prepare(&format!("SELECT id, url FROM urls WHERE {}", condition))?;
+        let rows = stmt.query_map(params![arg], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        let mut ids = Vec::new();
+        for row in rows {
+            let (id, url) = row?;
+            if keep(&url) {
+                ids.push(id);
+            }
+        }
+        ids
+    };
+
+    {
+        let mut deletes = Vec::new();
+        for (table, sql) in RELATED {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+                params![table],
+                |row| row.get(0),
+            )?;
+            if exists {
+                deletes.push(tx.prepare(sql)?);
+            }
+        }
+        deletes.push(tx.prepare("DELETE FROM urls WHERE id = ?1")?);
+
+        for id in &ids {
+            for stmt in &mut deletes {
+                stmt.execute(params![id])?;
+            }
+        }
+    }
+    tx.commit()?;
+
+    index.remove(&ids)
+}
+
+/// The full-screen, case-insensitive list every history menu uses.
+pub fn dmenu(prompt: &str) -> Dmenu<'_> {
+    Dmenu { prompt, ignore_case: true, theme: &crate::rofi::FULLSCREEN, ..Default::default() }
+}
+
+/// Opens `url` over CDP like every other action, switching to a tab that
+/// already shows it unless rofi's exit `code` asks for a new or incognito
+/// one. The browser is only launched with the page when CDP is down. Runs on
+/// a blocking thread, so the async calls are driven from here.
+fn open_url(url: &str, code: i32, config: &BrowserConfig) -> Result<()> {
+    let runtime = tokio::runtime::Handle::current();
+    if code != KB_INCOGNITO {
+        return runtime.block_on(crate::switch_or_open(url, code == KB_NEW_TAB, config));
+    }
+
+    let watch = runtime.block_on(crate::focus::Watch::start(config));
+    if !crate::cdp_ready(config) {
+        let mut switches = crate::launch::debugging_switches(config);
+        switches.push("--incognito".to_string());
+        crate::launch::launch_browser(config, &switches, &[url.to_string()])?;
+    } else {
+        runtime.block_on(crate::open_incognito(&[url.to_string()], config))?;
+    }
+    runtime.block_on(watch.focus_next());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn since(s: &str) -> String {
+        HistoryFilter { since: Some(Since::parse(s).unwrap()), ..Default::default() }.describe()
+    }
+
+    #[test]
+    fn parses_every_unit() {
+        assert_eq!(Since::parse("90s").unwrap(), Since::Ago { secs: 90, unit: 's' });
+        assert_eq!(Since::parse("30m").unwrap(), Since::Ago { secs: 1800, unit: 'm' });
+        assert_eq!(Since::parse("2h").unwrap(), Since::Ago { secs: 7200, unit: 'h' });
+        assert_eq!(Since::parse("2d").unwrap(), Since::Ago { secs: 172_800, unit: 'd' });
+        assert_eq!(Since::parse("1w").unwrap(), Since::Ago { secs: 604_800, unit: 'w' });
+        assert_eq!(Since::parse("today").unwrap(), Since::Today);
+        assert_eq!(Since::parse("week").unwrap(), Since::ThisWeek);
+    }
+
+    #[test]
+    fn parses_zero() {
+        assert_eq!(Since::parse("0m").unwrap(), Since::Ago { secs: 0, unit: 'm' });
+        assert!(Since::parse("0").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_or_unknown_units() {
+        assert!(Since::parse("90").is_err());
+        assert!(Since::parse("h").is_err());
+        assert!(Since::parse("").is_err());
+        assert!(Since::parse("2y").is_err());
+        assert!(Since::parse("2hh").is_err());
+        assert!(Since::parse("-2h").is_err());
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert!(Since::parse("99999999999999999999s").is_err());
+        assert!(Since::parse(&format!("{}w", i64::MAX / 604_800 + 1)).is_err());
+        assert!(Since::parse(&format!("{}w", i64::MAX / 604_800)).is_ok());
+    }
+
+    #[test]
+    fn describes_the_duration_as_typed() {
+        assert_eq!(since("90s"), "last 90s");
+        assert_eq!(since("120m"), "last 120m");
+        assert_eq!(since("48h"), "last 48h");
+        assert_eq!(since("14d"), "last 14d");
+        assert_eq!(since("2w"), "last 2w");
+        assert_eq!(since("today"), "today");
+    }
+}
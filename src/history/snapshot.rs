@@ -0,0 +1,128 @@
+use anyhow::Result;
+use rusqlite::{Connection, OpenFlags};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::config::BrowserConfig;
+
+/// A read-only view of the History DB.
+///
+/// When the browser holds its lock the DB (and any `-wal` file) is copied
+/// into a private per-process directory that is removed on drop; otherwise
+/// the file is opened in place with `immutable=1` so no lock is ever taken.
+pub struct Snapshot {
+    conn: Connection,
+    dir: Option<PathBuf>,
+}
+
+impl Snapshot {
+    pub fn open(config: &BrowserConfig) -> Result<Self> {
+        let source = Path::new(&config.history_path);
+
+        if !wal_path(source).exists() && !is_locked(source) {
+            let uri = format!(
+                "file:{}?mode=ro&immutable=1",
+                urlencoding::encode(&config.history_path).replace("%2F", "/")
+            );
+            let conn = Connection::open_with_flags(
+                uri,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+            )?;
+            return Ok(Self { conn, dir: None });
+        }
+
+        let dir = private_dir()?;
+        let copy = dir.join("History");
+        copy_private(source, &copy)?;
+        if wal_path(source).exists() {
+            copy_private(&wal_path(source), &wal_path(&copy))?;
+        }
+
+        // Opened read-write so SQLite can fold the copied WAL into the snapshot.
+        let conn = match Connection::open(&copy) {
+            Ok(conn) => conn,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                return Err(e.into());
+            }
+        };
+        Ok(Self { conn, dir: Some(dir) })
+    }
+}
+
+impl Deref for Snapshot {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Identifies the current state of the History DB and its WAL by their sizes
+/// and modification times, so an unchanged profile needn't be copied again.
+pub fn stamp(config: &BrowserConfig) -> i64 {
+    let source = Path::new(&config.history_path);
+    let mut hasher = DefaultHasher::new();
+    for path in [source.to_path_buf(), wal_path(source)] {
+        let metadata = fs::metadata(path).ok();
+        metadata.as_ref().map(fs::Metadata::len).hash(&mut hasher);
+        metadata.and_then(|m| m.modified().ok()).hash(&mut hasher);
+    }
+    hasher.finish() as i64
+}
+
+fn wal_path(db: &Path) -> PathBuf {
+    let mut path = db.as_os_str().to_owned();
+    path.push("-wal");
+    PathBuf::from(path)
+}
+
+/// Whether another process (the running browser) holds a lock on `db`.
+fn is_locked(db: &Path) -> bool {
+    let Ok(conn) = Connection::open_with_flags(db, OpenFlags::SQLITE_OPEN_READ_ONLY) else {
+        return true;
+    };
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+        .is_err()
+}
+
+/// Creates a fresh `0700` directory under the runtime dir for one snapshot.
+fn private_dir() -> Result<PathBuf> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let base = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    let dir = base.join(format!(
+        "brave-rofi-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let _ = fs::remove_dir_all(&dir);
+    fs::DirBuilder::new().mode(0o700).create(&dir)?;
+    Ok(dir)
+}
+
+fn copy_private(from: &Path, to: &Path) -> Result<()> {
+    let mut src = fs::File::open(from)?;
+    let mut dst = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(to)?;
+    std::io::copy(&mut src, &mut dst)?;
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Result};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use url::Url;
+use crate::config::BrowserConfig;
+
+/// Schemes we are willing to hand to the browser on its command line.
+const SCHEMES: [&str; 6] = ["http", "https", "file", "about", "chrome", "brave"];
+
+/// The only place the browser binary is started. Arguments go straight to
+/// `execve`, never through a shell; URLs are validated and placed after `--`
+/// so none can be read as a switch; the child gets its own session and no
+/// stdio so it outlives us and never writes to rofi's terminal.
+pub fn launch_browser(config: &BrowserConfig, switches: &[String], urls: &[String]) -> Result<()> {
+    let mut cmd = command(config, switches, urls)?;
+    // SAFETY: setsid is async-signal-safe and touches no parent state.
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    cmd.spawn()?;
+    Ok(())
+}
+
+/// The browser's argv: `switches`, then `--`, then each checked URL as a
+/// single argument.
+fn command(config: &BrowserConfig, switches: &[String], urls: &[String]) -> Result<Command> {
+    let urls = urls.iter().map(|url| check_url(url)).collect::<Result<Vec<_>>>()?;
+
+    let mut cmd = Command::new(&config.executable);
+    cmd.args(switches)
+        .arg("--")
+        .args(&urls)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    Ok(cmd)
+}
+
+/// Switches that make the browser listen for CDP on `config.cdp_port`.
+pub fn debugging_switches(config: &BrowserConfig) -> Vec<String> {
+    let mut switches = vec![format!("--remote-debugging-port={}", config.cdp_port)];
+    if let Some(dir) = &config.user_data_dir {
+        switches.push(format!("--user-data-dir={}", dir));
+    }
+    switches
+}
+
+/// `url` re-serialised by the URL parser, or an error if it isn't an
+/// absolute URL with one of the allowed schemes.
+pub fn check_url(url: &str) -> Result<String> {
+    if url.starts_with('-') || url.chars().any(char::is_control) {
+        return Err(anyhow!("Refusing to open '{}'", url.escape_default()));
+    }
+    let parsed = Url::parse(url).map_err(|e| anyhow!("Not a URL '{}': {}", url, e))?;
+    if !SCHEMES.contains(&parsed.scheme()) {
+        return Err(anyhow!("Refusing to open {} URL '{}'", parsed.scheme(), url));
+    }
+    // The parser percent-encodes quotes and the like everywhere but the host.
+    let host = parsed.host_str().unwrap_or_default();
+    if !host.chars().all(|c| c.is_ascii_alphanumeric() || "-._[]:".contains(c)) {
+        return Err(anyhow!("Refusing to open '{}': bad host", url));
+    }
+    Ok(parsed.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> BrowserConfig {
+        let mut config = BrowserConfig::from_env();
+        config.executable = "/usr/bin/brave".to_string();
+        config
+    }
+
+    #[test]
+    fn accepts_allowed_schemes() {
+        assert_eq!(check_url("https://example.com").unwrap(), "https://example.com/");
+        assert_eq!(check_url("file:///tmp/a.html").unwrap(), "file:///tmp/a.html");
+        assert_eq!(check_url("about:blank").unwrap(), "about:blank");
+    }
+
+    #[test]
+    fn rejects_other_schemes() {
+        assert!(check_url("javascript:alert(1)").is_err());
+        assert!(check_url("JavaScript:alert(1)").is_err());
+        assert!(check_url("data:text/html,<script>alert(1)</script>").is_err());
+        assert!(check_url("vbscript:msgbox").is_err());
+        assert!(check_url("ftp://example.com/").is_err());
+        assert!(check_url("example.com").is_err());
+    }
+
+    #[test]
+    fn rejects_switches() {
+        assert!(check_url("-incognito").is_err());
+        assert!(check_url("--user-data-dir=/tmp/x").is_err());
+        assert!(check_url("--renderer-cmd-prefix=sh -c id").is_err());
+    }
+
+    #[test]
+    fn rejects_control_characters() {
+        assert!(check_url("https://example.com/\nhttps://evil.example/").is_err());
+        assert!(check_url("https://example.com/\r").is_err());
+        assert!(check_url("https://example.com/\0").is_err());
+        assert!(check_url("https://example.com/\x1b[2J").is_err());
+        assert!(check_url("\thttps://example.com/").is_err());
+    }
+
+    #[test]
+    fn neutralises_shell_metacharacters() {
+        let url = check_url("https://example.com/a\"b'c;d$(id)`id`").unwrap();
+        assert!(url.starts_with("https://example.com/"));
+        assert!(check_url("https://exa`id`mple.com/").is_err());
+
+        for input in [
+            "https://example.com/a\"b'c;d$(id)`id`",
+            "https://example.com/?q=\"; rm -rf ~; \"",
+            "https://exa`id`mple.com/",
+            "https://\"example.com\"/",
+            "https://example.com/#$(touch /tmp/x)",
+        ] {
+            // Either refused, or re-serialised with nothing a shell could split on.
+            if let Ok(url) = check_url(input) {
+                assert!(!url.contains(['"', '`', ' ']), "{}", url);
+            }
+        }
+    }
+
+    #[test]
+    fn command_puts_each_url_after_separator() {
+        let switches = vec!["--remote-debugging-port=9222".to_string(), "--new-window".to_string()];
+        let urls = vec![
+            "https://example.com/a b;c".to_string(),
+            "https://example.org/$(id)".to_string(),
+        ];
+        let cmd = command(&config(), &switches, &urls).unwrap();
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_str().unwrap()).collect();
+
+        assert_eq!(cmd.get_program(), "/usr/bin/brave");
+        assert_eq!(
+            args,
+            [
+                "--remote-debugging-port=9222",
+                "--new-window",
+                "--",
+                "https://example.com/a%20b;c",
+                "https://example.org/$(id)",
+            ]
+        );
+    }
+
+    #[test]
+    fn command_refuses_any_bad_url() {
+        let urls = vec!["https://example.com/".to_string(), "--incognito".to_string()];
+        assert!(command(&config(), &[], &urls).is_err());
+
+        let urls = vec!["javascript:alert(1)".to_string()];
+        assert!(command(&config(), &[], &urls).is_err());
+    }
+}
@@ -2,12 +2,24 @@ mod bookmarks;
 mod history;
 mod search;
 mod config;
+mod cli;
+mod pages;
+mod launch;
+mod windows;
+mod groups;
+mod actions;
+mod clipboard;
+mod focus;
+mod audio;
+mod usage;
+mod suspend;
+mod rofi;
+mod chrome_time;
 
 use anyhow::{anyhow, Result};
 use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
-use std::process::{Command, Stdio};
-use std::io::Write;
+use std::process::Command;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message;
 use url::Url;
@@ -18,18 +30,70 @@ struct Tab {
     target_id: String,
     title: String,
     url: String,
+    /// Lives in the browser context `open_incognito` created.
+    incognito: bool,
+    window_id: Option<i64>,
+    /// Tab group label such as `Work · blue`, from the session file.
+    group: Option<String>,
+    audio: Option<audio::Audio>,
+    /// Only sampled for the tab list, when `config.show_usage` is set.
+    usage: Option<usage::Usage>,
+    /// Showing a placeholder page; `title` and `url` are the original's.
+    suspended: bool,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let config = BrowserConfig::from_env();
-    let tabs = get_tabs(&config).await?;
-    
-    let menu = build_menu(&tabs, &config);
-    let selection = show_rofi_menu(&menu, &config)?;
+
+    match cli::parse(std::env::args().skip(1))? {
+        cli::Cmd::Menu => {}
+        cli::Cmd::History(filter) => {
+            return tokio::task::spawn_blocking(move || history::show_history(&config, filter))
+                .await?;
+        }
+        cli::Cmd::Bookmarks(cmd) => return bookmarks_command(cmd, &config).await,
+        cli::Cmd::IndexPages => return pages::run_indexer(&config).await,
+        cli::Cmd::Find(words) => return pages::find(words, &config).await,
+        cli::Cmd::Open(cli::OpenFrom::Selection(selection), new_tab) => {
+            return open_from_clipboard(Some(selection), new_tab, &config).await;
+        }
+        cli::Cmd::Open(cli::OpenFrom::Text(text), new_tab) => {
+            return switch_or_open(&search::resolve(&text), new_tab, &config).await;
+        }
+        cli::Cmd::Tabs(cli::TabsCmd::Suspend(idle)) => return suspend::suspend_idle(idle, &config).await,
+        cli::Cmd::Copy(format) => {
+            let tabs = get_tabs(&config).await?;
+            let tab = active_tab(&tabs, &config).ok_or_else(|| anyhow!("No open tab to copy"))?;
+            return clipboard::copy_link(&tab.title, &tab.url, format);
+        }
+    }
+
+    let mut tabs = match get_tabs(&config).await {
+        Ok(tabs) => Some(tabs),
+        Err(e) if cdp_ready(&config) => return Err(e),
+        Err(_) => offer_launch(&config).await?,
+    };
+    if let Some(tabs) = &mut tabs {
+        // Without a readable session file tabs simply show no group.
+        let _ = groups::assign(tabs, &config);
+        audio::assign(tabs, &config).await;
+        // Sampling takes a moment, so only the tab list pays for it.
+        if config.show_usage {
+            if let Ok(mut ws) = connect_cdp(&config).await {
+                usage::assign(&mut ws, tabs).await;
+            }
+        }
+    }
+
+    let menu = match &tabs {
+        Some(tabs) => build_menu(tabs, &config),
+        None => build_offline_menu(&config),
+    };
+    let (code, selection) = show_rofi_menu(&menu, &config)?;
     
     if !selection.is_empty() {
-        handle_selection(selection, tabs, &config).await?;
+        handle_selection(selection, code, tabs.unwrap_or_default(), &config).await?;
     }
 
     Ok(())
@@ -39,14 +103,65 @@ async fn main() -> Result<()> {
 /* CDP                                          */
 /* ───────────────────────────────────────────── */
 
-async fn get_tabs(config: &BrowserConfig) -> Result<Vec<Tab>> {
+type CdpSocket = tokio_tungstenite::WebSocketStream<
+    tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>
+>;
+
+/// Opens a browser-level DevTools connection.
+async fn connect_cdp(config: &BrowserConfig) -> Result<CdpSocket> {
     let cdp_url = format!("http://localhost:{}/json/version", config.cdp_port);
     let version: serde_json::Value = reqwest_blocking(&cdp_url)?;
     let ws_url = version["webSocketDebuggerUrl"]
         .as_str()
         .ok_or_else(|| anyhow!("No debugger URL"))?;
 
-    let (mut ws, _) = connect_async(Url::parse(ws_url)?).await?;
+    let (ws, _) = connect_async(Url::parse(ws_url)?).await?;
+    Ok(ws)
+}
+
+/// Whether anything answers on the DevTools port.
+fn cdp_ready(config: &BrowserConfig) -> bool {
+    reqwest_blocking(&format!("http://localhost:{}/json/version", config.cdp_port)).is_ok()
+}
+
+/// Nothing listens on the DevTools port: offer to start the browser with it
+/// enabled and wait for it. `None` means carry on without tabs.
+async fn offer_launch(config: &BrowserConfig) -> Result<Option<Vec<Tab>>> {
+    if bookmarks::browser_running(config) {
+        rofi_message(&format!(
+            "{} is running without remote debugging; restart it with --remote-debugging-port={} to manage tabs",
+            config.name, config.cdp_port
+        ));
+        return Ok(None);
+    }
+
+    let launch = format!("Launch {} with remote debugging", config.name);
+    let choice = rofi_choose(
+        &format!("Nothing on port {}", config.cdp_port),
+        &[launch.as_str(), "Continue without tabs"],
+    );
+    if choice.is_empty() {
+        std::process::exit(0);
+    } else if choice != launch {
+        return Ok(None);
+    }
+
+    launch::launch_browser(config, &launch::debugging_switches(config), &[])?;
+    let mut delay = std::time::Duration::from_millis(100);
+    for _ in 0..10 {
+        tokio::time::sleep(delay).await;
+        if cdp_ready(config) {
+            return Ok(Some(get_tabs(config).await?));
+        }
+        delay = (delay * 2).min(std::time::Duration::from_secs(2));
+    }
+
+    rofi_message(&format!("{} didn't open port {} in time", config.name, config.cdp_port));
+    Ok(None)
+}
+
+async fn get_tabs(config: &BrowserConfig) -> Result<Vec<Tab>> {
+    let mut ws = connect_cdp(config).await?;
 
     // Enable discovery (REQUIRED FOR BRAVE)
     send_cdp(
@@ -69,44 +184,172 @@ async fn get_tabs(config: &BrowserConfig) -> Result<Vec<Tab>> {
     )
     .await?;
 
-    send_cdp(
-        &mut ws,
-        json!({
-            "id": 3,
-            "method": "Target.getTargets"
-        }),
-    )
-    .await?;
+    let targets = call_cdp(&mut ws, 3, "Target.getTargets", json!({})).await?;
+    let incognito = incognito_context(&mut ws, 4, config).await?;
 
+    let mut tabs: Vec<Tab> = targets["targetInfos"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Failed to fetch tabs"))?
+        .iter()
+        .filter(|t| t["type"] == "page"
+            && !t["url"].as_str().unwrap_or("").starts_with("chrome-extension://"))
+        .map(|t| {
+            let url = t["url"].as_str().unwrap_or("");
+            let original = suspend::original(url);
+            Tab {
+                target_id: t["targetId"].as_str().unwrap().to_string(),
+                title: original.as_ref().map_or(t["title"].as_str().unwrap_or("Untitled"), |(title, _)| title).to_string(),
+                url: original.as_ref().map_or(url, |(_, url)| url).to_string(),
+                incognito: incognito.is_some() && t["browserContextId"].as_str() == incognito.as_deref(),
+                window_id: None,
+                group: None,
+                audio: None,
+                usage: None,
+                suspended: original.is_some(),
+            }
+        })
+        .collect();
+    windows::assign(&mut ws, &mut tabs).await?;
+    Ok(tabs)
+}
+
+/// Sends a command and waits for its reply, skipping events on the way.
+async fn call_cdp(
+    ws: &mut CdpSocket,
+    id: u64,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value> {
+    send_cdp(ws, json!({ "id": id, "method": method, "params": params })).await?;
+    cdp_reply(ws, id, method).await
+}
+
+/// Like `call_cdp`, but for a page session from `attach_tab`.
+async fn call_tab(
+    ws: &mut CdpSocket,
+    id: u64,
+    session: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value> {
+    send_cdp(ws, json!({ "id": id, "sessionId": session, "method": method, "params": params })).await?;
+    cdp_reply(ws, id, method).await
+}
+
+/// Attaches to a tab so `Page`/`Runtime` commands can be sent to it.
+async fn attach_tab(ws: &mut CdpSocket, id: u64, target_id: &str) -> Result<String> {
+    let reply = call_cdp(ws, id, "Target.attachToTarget", json!({ "targetId": target_id, "flatten": true })).await?;
+    reply["sessionId"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Could not attach to tab {}", target_id))
+}
+
+async fn cdp_reply(ws: &mut CdpSocket, id: u64, method: &str) -> Result<serde_json::Value> {
     while let Some(msg) = ws.next().await {
         let msg = msg?;
-        if let Ok(txt) = msg.to_text() {
-            let v: serde_json::Value = serde_json::from_str(txt)?;
-            if let Some(targets) = v["result"]["targetInfos"].as_array() {
-                let tabs = targets
-                    .iter()
-                    .filter(|t| t["type"] == "page"
-                        && !t["url"].as_str().unwrap_or("").starts_with("chrome-extension://"))
-                    .map(|t| Tab {
-                        target_id: t["targetId"].as_str().unwrap().to_string(),
-                        title: t["title"].as_str().unwrap_or("Untitled").to_string(),
-                        url: t["url"].as_str().unwrap_or("").to_string(),
-                    })
-                    .collect();
-                return Ok(tabs);
-            }
+        let Ok(mut v) = serde_json::from_str::<serde_json::Value>(msg.to_text().unwrap_or_default()) else {
+            continue;
+        };
+        if v["id"] != id {
+            continue;
+        }
+        if let Some(error) = v.get("error") {
+            return Err(anyhow!("{} failed: {}", method, error["message"]));
         }
+        return Ok(v["result"].take());
     }
+    Err(anyhow!("DevTools connection closed during {}", method))
+}
 
-    Err(anyhow!("Failed to fetch tabs"))
+/// Sends several commands at once, numbered from `first_id`, and collects
+/// their results in order; a command that fails yields `null`.
+async fn call_cdp_batch(
+    ws: &mut CdpSocket,
+    first_id: u64,
+    calls: Vec<(&str, serde_json::Value)>,
+) -> Result<Vec<serde_json::Value>> {
+    for (id, (method, params)) in (first_id..).zip(&calls) {
+        send_cdp(ws, json!({ "id": id, "method": method, "params": params })).await?;
+    }
+    batch_replies(ws, first_id, calls.len(), None).await
 }
 
-async fn send_cdp(
-    ws: &mut tokio_tungstenite::WebSocketStream<
-        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>
-    >,
-    msg: serde_json::Value,
-) -> Result<()> {
+/// `call_cdp_batch` that stops waiting at `deadline`; commands still
+/// unanswered then yield `null` too, so one hung tab can't hide the rest.
+async fn call_cdp_batch_until(
+    ws: &mut CdpSocket,
+    first_id: u64,
+    calls: Vec<(&str, serde_json::Value)>,
+    deadline: tokio::time::Instant,
+) -> Result<Vec<serde_json::Value>> {
+    for (id, (method, params)) in (first_id..).zip(&calls) {
+        send_cdp(ws, json!({ "id": id, "method": method, "params": params })).await?;
+    }
+    batch_replies(ws, first_id, calls.len(), Some(deadline)).await
+}
+
+/// `call_cdp_batch` for commands to attached tabs, as `(session, method, params)`.
+async fn call_tab_batch(
+    ws: &mut CdpSocket,
+    first_id: u64,
+    calls: Vec<(&str, &str, serde_json::Value)>,
+) -> Result<Vec<serde_json::Value>> {
+    for (id, (session, method, params)) in (first_id..).zip(&calls) {
+        send_cdp(ws, json!({ "id": id, "sessionId": session, "method": method, "params": params })).await?;
+    }
+    batch_replies(ws, first_id, calls.len(), None).await
+}
+
+/// `call_tab_batch` with a deadline, like `call_cdp_batch_until`.
+async fn call_tab_batch_until(
+    ws: &mut CdpSocket,
+    first_id: u64,
+    calls: Vec<(&str, &str, serde_json::Value)>,
+    deadline: tokio::time::Instant,
+) -> Result<Vec<serde_json::Value>> {
+    for (id, (session, method, params)) in (first_id..).zip(&calls) {
+        send_cdp(ws, json!({ "id": id, "sessionId": session, "method": method, "params": params })).await?;
+    }
+    batch_replies(ws, first_id, calls.len(), Some(deadline)).await
+}
+
+async fn batch_replies(
+    ws: &mut CdpSocket,
+    first_id: u64,
+    count: usize,
+    deadline: Option<tokio::time::Instant>,
+) -> Result<Vec<serde_json::Value>> {
+    let mut results = vec![serde_json::Value::Null; count];
+    let mut pending = count;
+    while pending > 0 {
+        let next = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, ws.next()).await {
+                Ok(next) => next,
+                Err(_) => break,
+            },
+            None => ws.next().await,
+        };
+        let Some(msg) = next else {
+            return Err(anyhow!("DevTools connection closed"));
+        };
+        let Ok(mut v) = serde_json::from_str::<serde_json::Value>(msg?.to_text().unwrap_or_default()) else {
+            continue;
+        };
+        let Some(slot) = v["id"]
+            .as_u64()
+            .and_then(|id| id.checked_sub(first_id))
+            .and_then(|i| results.get_mut(i as usize))
+        else {
+            continue;
+        };
+        *slot = v["result"].take();
+        pending -= 1;
+    }
+    Ok(results)
+}
+
+async fn send_cdp(ws: &mut CdpSocket, msg: serde_json::Value) -> Result<()> {
     ws.send(Message::Text(msg.to_string())).await?;
     Ok(())
 }
@@ -116,79 +359,133 @@ async fn send_cdp(
 /* ───────────────────────────────────────────── */
 
 fn build_menu(tabs: &[Tab], config: &BrowserConfig) -> String {
+    let incognito = tabs.iter().filter(|t| t.incognito).count();
     let mut menu = String::new();
-    menu.push_str(&format!("Tabs: {}\n", tabs.len()));
+    let suspended = tabs.iter().filter(|t| t.suspended).count();
+    match suspended {
+        0 => menu.push_str(&format!("Tabs: {}\n", tabs.len() - incognito)),
+        n => menu.push_str(&format!("Tabs: {} ({} suspended)\n", tabs.len() - incognito, n)),
+    }
     menu.push_str("────\n");
     menu.push_str(&format!("Search ({})\n", config.name));
     menu.push_str("────\n");
 
+    // Numbering runs on through the incognito section so it still indexes `tabs`.
+    let order = windows::window_order(tabs);
     for (i, tab) in tabs.iter().enumerate() {
-        menu.push_str(&format!("{}. {} - {}\n", i + 1, tab.title, tab.url));
+        let previous = i.checked_sub(1).map(|p| &tabs[p]);
+        if tab.incognito && !previous.is_some_and(|p| p.incognito) {
+            menu.push_str("────\n");
+            menu.push_str(&format!("Incognito tabs: {}\n", incognito));
+        }
+        if let Some(n) = order.iter().position(|w| Some(*w) == tab.window_id) {
+            if previous.is_none_or(|p| p.window_id != tab.window_id || p.incognito != tab.incognito) {
+                let count = tabs.iter().filter(|t| t.window_id == tab.window_id).count();
+                menu.push_str(&windows::heading(n + 1, count));
+                menu.push('\n');
+            }
+        }
+        menu.push_str(&format!("{}. ", i + 1));
+        if let Some(usage) = tab.usage {
+            menu.push_str(&format!("{}  ", usage.column()));
+        }
+        let icon = match tab.audio {
+            Some(audio) => audio.icon(),
+            None if tab.suspended => "💤 ",
+            None => "",
+        };
+        menu.push_str(&format!("{}{} - {}", icon, tab.title, tab.url));
+        if let Some(group) = &tab.group {
+            menu.push_str(&format!("  [{}]", group));
+        }
+        menu.push('\n');
     }
 
     menu.push_str("────\n");
+    if tabs.iter().any(|t| t.group.is_some()) {
+        menu.push_str("- Tab groups…\n");
+    }
+    if tabs.iter().any(|t| t.audio.is_some()) {
+        menu.push_str("- Go to playing tab\n");
+        menu.push_str("- Mute all\n");
+    }
     menu.push_str("- Bookmarks\n");
     menu.push_str("- Bookmarks incognito\n");
     menu.push_str("- New Tab\n");
+    menu.push_str("- Open from clipboard\n");
     menu.push_str("- Close Tab\n");
+    menu.push_str("- Close heaviest tabs\n");
+    if tabs.iter().any(suspend::candidate) {
+        menu.push_str("- Suspend tabs\n");
+    }
     menu.push_str("- Close ALL Tabs\n");
+    if incognito > 0 {
+        menu.push_str("- Close incognito tabs\n");
+    }
     menu.push_str("- Search in incognito\n");
     menu.push_str("- History\n");
+    menu.push_str("- Find in pages\n");
     menu.push_str("- Exit\n");
     
     menu
 }
 
-fn show_rofi_menu(menu: &str, config: &BrowserConfig) -> Result<String> {
-    let mut child = Command::new("rofi")
-        .args([
-            "-dmenu",
-            "-i",
-            "-p", &format!("{} Tabs", config.name),
-            "-theme-str", "window { fullscreen: true; } mainbox { padding: 2%; }"
-        ])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()?;
-    
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin.write_all(menu.as_bytes())?;
-        stdin.flush()?;
-        drop(stdin);
+/// What still works without CDP; opening falls back to a new browser process.
+fn build_offline_menu(config: &BrowserConfig) -> String {
+    format!("Search ({})\n────\n- Open from clipboard\n- Bookmarks\n- History\n- Exit\n", config.name)
+}
+
+/// rofi's exit code and selection; `KB_TAB_ACTIONS` asks for the tab submenu.
+fn show_rofi_menu(menu: &str, config: &BrowserConfig) -> Result<(i32, String)> {
+    rofi::Dmenu {
+        prompt: &format!("{} Tabs", config.name),
+        mesg: Some(">>> Alt+1 on a tab: more actions · select a window heading to arrange it · type a bookmark, tag or search alias and Ctrl+Enter to open it"),
+        keys: &["Alt+1"],
+        ignore_case: true,
+        theme: &rofi::FULLSCREEN,
+        ..Default::default()
     }
-    
-    let output = child.wait_with_output()?;
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    .run(menu)
 }
 
 /* ───────────────────────────────────────────── */
 /* Actions                                      */
 /* ───────────────────────────────────────────── */
 
-async fn handle_selection(sel: String, tabs: Vec<Tab>, config: &BrowserConfig) -> Result<()> {
-    if sel.starts_with("Search (") {
+/// rofi exit code for `-kb-custom-1` in the main menu.
+const KB_TAB_ACTIONS: i32 = 10;
+
+async fn handle_selection(sel: String, code: i32, tabs: Vec<Tab>, config: &BrowserConfig) -> Result<()> {
+    if let Some(window) = windows::parse_heading(&sel, &tabs) {
+        windows::window_menu(window, &tabs, config).await?;
+    } else if sel.starts_with("Search (") {
         search::regular::run(config).await?;
+    } else if sel == "- Tab groups…" {
+        groups::group_menu(&tabs, config).await?;
+    } else if sel == "- Go to playing tab" {
+        audio::go_to_playing(&tabs, config).await?;
+    } else if sel == "- Mute all" {
+        audio::mute_all(&tabs, config).await?;
     } else if sel == "- Bookmarks" {
-        tokio::task::spawn_blocking({
-            let cfg = config.clone();
-            move || bookmarks::show_bookmarks(false, &cfg)
-        });
+        bookmarks::show_bookmarks(false, config).await?;
     } else if sel == "- Bookmarks incognito" {
-        tokio::task::spawn_blocking({
-            let cfg = config.clone();
-            move || bookmarks::show_bookmarks(true, &cfg)
-        });
+        bookmarks::show_bookmarks(true, config).await?;
     } else if sel == "- History" {
         tokio::task::spawn_blocking({
             let cfg = config.clone();
-            move || history::show_history(&cfg)
-        });
+            move || history::show_history(&cfg, history::HistoryFilter::default())
+        })
+        .await??;
+    } else if sel == "- Find in pages" {
+        pages::find(None, config).await?;
     } else if sel == "- Search in incognito" {
         search::incognito::run(config).await?;
     } else if sel == "- New Tab" {
-        open_tab("about:blank", config).await?;
-        tokio::time::sleep(std::time::Duration::from_millis(150)).await;
-        focus_browser(config);
+        switch_or_open("about:blank", true, config).await?;
+    } else if sel == "- Open from clipboard" {
+        if let Err(e) = open_from_clipboard(None, false, config).await {
+            rofi_message(&e.to_string());
+        }
     } else if sel == "- Close Tab" {
         let tab_options: Vec<String> = tabs.iter()
             .enumerate()
@@ -206,6 +503,10 @@ async fn handle_selection(sel: String, tabs: Vec<Tab>, config: &BrowserConfig) -
                 }
             }
         }
+    } else if sel == "- Suspend tabs" {
+        suspend::suspend_menu(&tabs, config).await?;
+    } else if sel == "- Close heaviest tabs" {
+        usage::close_heaviest(&tabs, config).await?;
     } else if sel == "- Close ALL Tabs" {
         let confirm = rofi_confirm("Close ALL tabs?");
         if confirm == "YES" {
@@ -214,60 +515,107 @@ async fn handle_selection(sel: String, tabs: Vec<Tab>, config: &BrowserConfig) -
                 let _ = close_tab(&t.target_id, config).await;
             }
         }
+    } else if sel == "- Close incognito tabs" {
+        close_incognito(config).await?;
     } else if sel == "- Exit" {
         std::process::exit(0);
-    } else if sel.chars().next().map_or(false, |c| c.is_numeric()) {
+    } else if sel.chars().next().is_some_and(|c| c.is_numeric()) {
         let idx: usize = sel
             .split('.')
             .next()
             .ok_or_else(|| anyhow!("Failed to parse selection"))?
             .parse::<usize>()?;
         let idx = idx.saturating_sub(1);
-        if let Some(tab) = tabs.get(idx) {
-            activate_tab(&tab.target_id, config).await?;
-            tokio::time::sleep(std::time::Duration::from_millis(150)).await;
-            find_and_focus_browser_window(&tab.title, config);
+        if let Some(tab) = tabs.get(idx).filter(|_| code == KB_TAB_ACTIONS) {
+            actions::tab_menu(tab, &tabs, config).await?;
+        } else if let Some(tab) = tabs.get(idx) {
+            focus::tab(&tab.target_id, &tab.title, config).await?;
+        }
+    } else if !sel.is_empty() {
+        // Typed text: a bookmark name or tag, or a search alias and query.
+        if !bookmarks::open_keyword(&sel, false, config).await? {
+            rofi_message(&format!("No bookmark or search alias '{}'", sel));
         }
     }
 
     Ok(())
 }
 
+async fn bookmarks_command(cmd: cli::BookmarksCmd, config: &BrowserConfig) -> Result<()> {
+    match cmd {
+        cli::BookmarksCmd::Menu => bookmarks::show_bookmarks(false, config).await,
+        cli::BookmarksCmd::Add(name, tags) => bookmarks::add_current_tab(name, &tags, config).await,
+        cli::BookmarksCmd::Open(keyword) => {
+            if !bookmarks::open_keyword(&keyword, false, config).await? {
+                return Err(anyhow!("No bookmark or search alias '{}'", keyword));
+            }
+            Ok(())
+        }
+        cli::BookmarksCmd::OpenAll(group, mode) => bookmarks::open_group(&group, mode, config).await,
+        cli::BookmarksCmd::Rename(name, new_name) => {
+            bookmarks::rename(&bookmarks::find(&name, config)?, &new_name, config)
+        }
+        cli::BookmarksCmd::Move(name, folder) => {
+            bookmarks::move_to(&bookmarks::find(&name, config)?, &folder, config)
+        }
+        cli::BookmarksCmd::Delete(name) => {
+            bookmarks::delete(&bookmarks::find(&name, config)?, config)
+        }
+        cli::BookmarksCmd::Export(format, output) => {
+            let text = bookmarks::export(format, config)?;
+            match output {
+                Some(path) => std::fs::write(path, text)?,
+                None => print!("{}", text),
+            }
+            Ok(())
+        }
+        cli::BookmarksCmd::Import(path, into) => {
+            let added = bookmarks::import(&path, into, config)?;
+            println!("Imported {} bookmarks", added);
+            Ok(())
+        }
+        cli::BookmarksCmd::Check(options, fix) => {
+            // File order rather than `load_all`'s sorting, so the entry added
+            // first is the one kept when URLs repeat.
+            let (list, malformed) = bookmarks::load_unsorted(config)?;
+            let findings = bookmarks::check(&list, malformed, &options)?;
+            if fix {
+                return bookmarks::review(findings, config);
+            }
+            for finding in &findings {
+                println!("{}", finding.label());
+            }
+            if findings.is_empty() {
+                println!("No problems found");
+            }
+            Ok(())
+        }
+    }
+}
+
 /* ───────────────────────────────────────────── */
 /* Helpers                                      */
 /* ───────────────────────────────────────────── */
 fn rofi_confirm(prompt: &str) -> String {
-    let mut child = Command::new("rofi")
-        .args(["-dmenu", "-p", prompt])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()
-        .unwrap();
-    
-    if let Some(mut stdin) = child.stdin.take() {
-        let _ = stdin.write_all(b"NO\nYES\n");
-        let _ = stdin.flush();
-    }
-    
-    let output = child.wait_with_output().unwrap();
-    String::from_utf8_lossy(&output.stdout).trim().to_string()
+    rofi_choose(prompt, &["NO", "YES"])
+}
+
+fn rofi_choose(prompt: &str, options: &[&str]) -> String {
+    rofi::Dmenu { prompt, ..Default::default() }
+        .run(&options.join("\n"))
+        .map(|(_, choice)| choice)
+        .unwrap_or_default()
+}
+
+fn rofi_message(message: &str) {
+    let _ = Command::new("rofi").args(["-e", message]).output();
 }
 
 fn rofi_multi_select(prompt: &str, options: &str) -> String {
-    let mut child = Command::new("rofi")
-        .args(["-dmenu", "-multi-select", "-p", prompt])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()
-        .unwrap();
-    
-    if let Some(mut stdin) = child.stdin.take() {
-        let _ = stdin.write_all(options.as_bytes());
-        let _ = stdin.flush();
-    }
-    
-    let output = child.wait_with_output().unwrap();
-    String::from_utf8_lossy(&output.stdout).trim().to_string()
+    rofi::Dmenu { prompt, multi_select: true, ..Default::default() }
+        .run(options)
+        .map(|(_, chosen)| chosen)
+        .unwrap_or_default()
 }
 
 fn focus_browser(config: &BrowserConfig) {
@@ -304,6 +652,41 @@ fn find_and_focus_browser_window(tab_title: &str, config: &BrowserConfig) {
     }
 }
 
+/// The tab shown in the focused browser window, falling back to any browser
+/// window's visible tab and finally to the first page CDP reports.
+fn active_tab<'a>(tabs: &'a [Tab], config: &BrowserConfig) -> Option<&'a Tab> {
+    let mut names = Vec::new();
+    if let Ok(output) = Command::new("i3-msg").args(["-t", "get_tree"]).output() {
+        if let Ok(tree) = serde_json::from_slice::<serde_json::Value>(&output.stdout) {
+            names = find_browser_windows(&tree, config);
+            if let Some(focused) = find_focused_window(&tree) {
+                names.sort_by_key(|(id, _)| *id != focused);
+            }
+        }
+    }
+
+    names
+        .iter()
+        .find_map(|(_, name)| {
+            tabs.iter()
+                .find(|t| !t.title.is_empty() && name.starts_with(&t.title))
+        })
+        .or_else(|| tabs.first())
+}
+
+fn find_focused_window(node: &serde_json::Value) -> Option<u64> {
+    if node["focused"].as_bool() == Some(true) {
+        if let Some(window) = node["window"].as_u64() {
+            return Some(window);
+        }
+    }
+    node["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find_map(find_focused_window)
+}
+
 fn find_browser_windows(node: &serde_json::Value, config: &BrowserConfig) -> Vec<(u64, String)> {
     let mut windows = Vec::new();
     
@@ -325,9 +708,137 @@ fn find_browser_windows(node: &serde_json::Value, config: &BrowserConfig) -> Vec
 }
 
 async fn open_tab(url: &str, config: &BrowserConfig) -> Result<()> {
+    if !cdp_ready(config) {
+        // Without DevTools a new browser process opens it instead.
+        return launch::launch_browser(config, &launch::debugging_switches(config), &[url.to_string()]);
+    }
     cdp_simple("Target.createTarget", json!({ "url": url }), config).await
 }
 
+/// Activates a regular tab already showing `url` (as compared by
+/// `config.url_match`); `false` if there is none or CDP is down.
+async fn switch_to_existing(url: &str, config: &BrowserConfig) -> Result<bool> {
+    if !cdp_ready(config) {
+        return Ok(false);
+    }
+    let tabs = get_tabs(config).await?;
+    let Some(tab) = tabs.iter().find(|t| !t.incognito && config.url_match.same(&t.url, url)) else {
+        return Ok(false);
+    };
+    focus::tab(&tab.target_id, &tab.title, config).await?;
+    Ok(true)
+}
+
+/// Switches to the tab already showing `url`, or opens it in a new one;
+/// `new_tab` skips the lookup.
+async fn switch_or_open(url: &str, new_tab: bool, config: &BrowserConfig) -> Result<()> {
+    if !new_tab && switch_to_existing(url, config).await? {
+        return Ok(());
+    }
+    if cdp_ready(config) {
+        return focus::open(json!({ "url": url }), config).await;
+    }
+    let watch = focus::Watch::start(config).await;
+    open_tab(url, config).await?;
+    watch.focus_next().await;
+    Ok(())
+}
+
+/// Opens the link in (or searches for) the clipboard's text, falling back to
+/// the primary selection when the clipboard is empty.
+async fn open_from_clipboard(
+    selection: Option<clipboard::Selection>,
+    new_tab: bool,
+    config: &BrowserConfig,
+) -> Result<()> {
+    let text = match selection {
+        Some(selection) => clipboard::paste(selection)?,
+        None => match clipboard::paste(clipboard::Selection::Clipboard)? {
+            text if text.is_empty() => clipboard::paste(clipboard::Selection::Primary)?,
+            text => text,
+        },
+    };
+    if text.is_empty() {
+        return Err(anyhow!("Nothing to open: the clipboard is empty"));
+    }
+    switch_or_open(&search::resolve(&text), new_tab, config).await
+}
+
+/// Opens `urls` together in one new window. New targets land in the last
+/// active window, so the window's first tab is activated before the rest open.
+async fn open_window(urls: &[String], config: &BrowserConfig) -> Result<()> {
+    let Some((first, rest)) = urls.split_first() else { return Ok(()) };
+    let mut ws = connect_cdp(config).await?;
+    let created = call_cdp(&mut ws, 1, "Target.createTarget", json!({ "url": first, "newWindow": true })).await?;
+    let target = created["targetId"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Target.createTarget returned no target"))?;
+    call_cdp(&mut ws, 2, "Target.activateTarget", json!({ "targetId": target })).await?;
+    for (id, url) in (3..).zip(rest) {
+        call_cdp(&mut ws, id, "Target.createTarget", json!({ "url": url })).await?;
+    }
+    Ok(())
+}
+
+/// The browser context `open_incognito` created, if it is still open. Only
+/// its id is recorded, so contexts made by other tools are left alone.
+async fn incognito_context(ws: &mut CdpSocket, id: u64, config: &BrowserConfig) -> Result<Option<String>> {
+    let Ok(ours) = std::fs::read_to_string(config.cache_path("incognito-context")?) else {
+        return Ok(None);
+    };
+    let ours = ours.trim();
+    let contexts = call_cdp(ws, id, "Target.getBrowserContexts", json!({})).await?;
+    let open = contexts["browserContextIds"]
+        .as_array()
+        .is_some_and(|ids| ids.iter().any(|id| id == ours));
+    Ok(open.then(|| ours.to_string()))
+}
+
+/// Opens `urls` in the incognito browser context, creating it on first use.
+async fn open_incognito(urls: &[String], config: &BrowserConfig) -> Result<()> {
+    let mut ws = connect_cdp(config).await?;
+    let context = match incognito_context(&mut ws, 1, config).await? {
+        Some(id) => id,
+        None => {
+            // Kept after we disconnect so later invocations reuse it.
+            let created = call_cdp(
+                &mut ws,
+                2,
+                "Target.createBrowserContext",
+                json!({ "disposeOnDetach": false }),
+            )
+            .await?;
+            let id = created["browserContextId"]
+                .as_str()
+                .ok_or_else(|| anyhow!("No browser context id"))?
+                .to_string();
+            std::fs::write(config.cache_path("incognito-context")?, &id)?;
+            id
+        }
+    };
+
+    for (id, url) in (3..).zip(urls) {
+        call_cdp(
+            &mut ws,
+            id,
+            "Target.createTarget",
+            json!({ "url": url, "browserContextId": context }),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Disposing a browser context closes every tab in it at once.
+async fn close_incognito(config: &BrowserConfig) -> Result<()> {
+    let mut ws = connect_cdp(config).await?;
+    if let Some(context) = incognito_context(&mut ws, 1, config).await? {
+        call_cdp(&mut ws, 2, "Target.disposeBrowserContext", json!({ "browserContextId": context })).await?;
+    }
+    let _ = std::fs::remove_file(config.cache_path("incognito-context")?);
+    Ok(())
+}
+
 async fn activate_tab(id: &str, config: &BrowserConfig) -> Result<()> {
     cdp_simple("Target.activateTarget", json!({ "targetId": id }), config).await
 }
@@ -337,12 +848,7 @@ async fn close_tab(id: &str, config: &BrowserConfig) -> Result<()> {
 }
 
 async fn cdp_simple(method: &str, params: serde_json::Value, config: &BrowserConfig) -> Result<()> {
-    let cdp_url = format!("http://localhost:{}/json/version", config.cdp_port);
-    let version: serde_json::Value = reqwest_blocking(&cdp_url)?;
-    let ws_url = version["webSocketDebuggerUrl"]
-        .as_str()
-        .ok_or_else(|| anyhow!("No debugger URL"))?;
-    let (mut ws, _) = connect_async(Url::parse(ws_url)?).await?;
+    let mut ws = connect_cdp(config).await?;
     send_cdp(&mut ws, json!({ "id": 1, "method": method, "params": params })).await?;
     Ok(())
 }
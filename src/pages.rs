@@ -0,0 +1,247 @@
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use rusqlite::{params, Connection};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::os::unix::fs::OpenOptionsExt;
+use crate::config::BrowserConfig;
+use crate::{call_cdp, connect_cdp, send_cdp, switch_or_open};
+
+/// Upper bound on the text kept per page; enough for articles, not for dumps.
+const MAX_TEXT_CHARS: usize = 200_000;
+
+/// Evaluated in each page once it has loaded.
+const CAPTURE_EXPRESSION: &str = "({ \
+    url: location.href, \
+    title: document.title, \
+    text: document.body ? document.body.innerText : '' \
+})";
+
+const SCHEMA: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS pages USING fts5 (
+    url UNINDEXED, title, body, tokenize = 'porter unicode61'
+);
+"#;
+
+/// Local FTS5 store of page text captured by [`run_indexer`].
+pub struct PageStore {
+    conn: Connection,
+}
+
+impl PageStore {
+    pub fn open(config: &BrowserConfig) -> Result<Self> {
+        let path = config.cache_path("pages.sqlite")?;
+        fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(0o600)
+            .open(&path)?;
+
+        let conn = Connection::open(&path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    fn put(&self, url: &str, title: &str, text: &str) -> Result<()> {
+        let text: String = text.chars().take(MAX_TEXT_CHARS).collect();
+        self.conn.execute("DELETE FROM pages WHERE url = ?1", params![url])?;
+        self.conn.execute(
+            "INSERT INTO pages (url, title, body) VALUES (?1, ?2, ?3)",
+            params![url, title, text],
+        )?;
+        Ok(())
+    }
+
+    /// Best matches first, each as `(title, snippet, url)`.
+    fn search(&self, words: &str) -> Result<Vec<(String, String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT title, snippet(pages, 2, '', '', '…', 12), url
+             FROM pages WHERE pages MATCH ?1
+             ORDER BY bm25(pages, 0.0, 5.0, 1.0)
+             LIMIT 200",
+        )?;
+        let rows = stmt.query_map(params![crate::history::fts_query(words)], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+enum Pending {
+    Attach(String),
+    Capture,
+}
+
+/// Watches every page target and stores its text each time it finishes
+/// loading. Runs until the browser goes away.
+///
+/// Only tabs in the profile's default browser context are indexed, so
+/// incognito pages (and any other tool's contexts) never reach the disk.
+pub async fn run_indexer(config: &BrowserConfig) -> Result<()> {
+    let store = PageStore::open(config)?;
+    let mut ws = connect_cdp(config).await?;
+
+    let contexts = call_cdp(&mut ws, 1, "Target.getBrowserContexts", json!({})).await?;
+    let profile = contexts["defaultBrowserContextId"]
+        .as_str()
+        .ok_or_else(|| anyhow!("{} does not report its default browser context", config.name))?
+        .to_string();
+
+    let mut next_id: u64 = 2;
+    let mut pending: HashMap<u64, Pending> = HashMap::new();
+    // Targets we've attached (or are attaching) to, and their session ids.
+    let mut watched: HashSet<String> = HashSet::new();
+    let mut sessions: HashMap<String, String> = HashMap::new();
+
+    send_cdp(
+        &mut ws,
+        json!({
+            "id": next_id,
+            "method": "Target.setDiscoverTargets",
+            "params": { "discover": true }
+        }),
+    )
+    .await?;
+    next_id += 1;
+
+    while let Some(msg) = ws.next().await {
+        let msg = msg?;
+        let Ok(txt) = msg.to_text() else { continue };
+        let Ok(v) = serde_json::from_str::<serde_json::Value>(txt) else { continue };
+
+        match v["method"].as_str() {
+            Some("Target.targetCreated") | Some("Target.targetInfoChanged") => {
+                let info = &v["params"]["targetInfo"];
+                let target_id = info["targetId"].as_str().unwrap_or_default();
+                let url = info["url"].as_str().unwrap_or_default();
+                if info["type"] != "page"
+                    || info["browserContextId"] != profile.as_str()
+                    || !(url.starts_with("http://") || url.starts_with("https://"))
+                    || watched.contains(target_id)
+                {
+                    continue;
+                }
+                send_cdp(
+                    &mut ws,
+                    json!({
+                        "id": next_id,
+                        "method": "Target.attachToTarget",
+                        "params": { "targetId": target_id, "flatten": true }
+                    }),
+                )
+                .await?;
+                watched.insert(target_id.to_string());
+                pending.insert(next_id, Pending::Attach(target_id.to_string()));
+                next_id += 1;
+            }
+            Some("Target.targetDestroyed") => {
+                if let Some(target_id) = v["params"]["targetId"].as_str() {
+                    watched.remove(target_id);
+                }
+            }
+            Some("Target.detachedFromTarget") => {
+                if let Some(session) = v["params"]["sessionId"].as_str() {
+                    if let Some(target_id) = sessions.remove(session) {
+                        watched.remove(&target_id);
+                    }
+                }
+            }
+            Some("Page.loadEventFired") => {
+                if let Some(session) = v["sessionId"].as_str() {
+                    capture(&mut ws, session, next_id).await?;
+                    pending.insert(next_id, Pending::Capture);
+                    next_id += 1;
+                }
+            }
+            Some(_) => {}
+            None => {
+                let Some(id) = v["id"].as_u64() else { continue };
+                match pending.remove(&id) {
+                    Some(Pending::Attach(target_id)) => {
+                        let Some(session) = v["result"]["sessionId"].as_str() else {
+                            watched.remove(&target_id);
+                            continue;
+                        };
+                        sessions.insert(session.to_string(), target_id);
+
+                        send_cdp(
+                            &mut ws,
+                            json!({ "id": next_id, "sessionId": session, "method": "Page.enable" }),
+                        )
+                        .await?;
+                        next_id += 1;
+
+                        // The page may already be loaded, so capture it right away too.
+                        capture(&mut ws, session, next_id).await?;
+                        pending.insert(next_id, Pending::Capture);
+                        next_id += 1;
+                    }
+                    Some(Pending::Capture) => {
+                        let page = &v["result"]["result"]["value"];
+                        let url = page["url"].as_str().unwrap_or_default();
+                        let text = page["text"].as_str().unwrap_or_default();
+                        if url.starts_with("http") && !text.trim().is_empty() {
+                            store.put(url, page["title"].as_str().unwrap_or_default(), text)?;
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn capture(ws: &mut crate::CdpSocket, session: &str, id: u64) -> Result<()> {
+    send_cdp(
+        ws,
+        json!({
+            "id": id,
+            "sessionId": session,
+            "method": "Runtime.evaluate",
+            "params": { "expression": CAPTURE_EXPRESSION, "returnByValue": true }
+        }),
+    )
+    .await
+}
+
+/// Searches captured page text and switches to (or opens) the chosen page.
+pub async fn find(words: Option<String>, config: &BrowserConfig) -> Result<()> {
+    let words = match words {
+        Some(words) => words,
+        None => crate::search::prompt("Find in pages"),
+    };
+    if words.is_empty() {
+        return Ok(());
+    }
+
+    let store = PageStore::open(config)?;
+    let results = store.search(&words)?;
+    if results.is_empty() {
+        crate::rofi_message(&format!(
+            "No captured pages match \"{}\" (is `bbr index-pages` running?)",
+            words
+        ));
+        return Ok(());
+    }
+
+    let cols: usize = 40;
+    let menu = results
+        .iter()
+        .map(|(title, snippet, url)| {
+            let title = title.chars().take(cols).collect::<String>();
+            let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+            format!("{:<width$}  {}  {}", title, snippet, url, width = cols)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let (_, selection) = crate::history::dmenu(&format!("Pages: {}", words)).run(&menu)?;
+    let Some(url) = selection.rsplit(' ').next().filter(|u| u.starts_with("http")) else {
+        return Ok(());
+    };
+
+    switch_or_open(url, false, config).await
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// `-theme-str` for the full-screen tab, history and page lists.
+pub const FULLSCREEN: [&str; 2] = ["-theme-str", "window { fullscreen: true; } mainbox { padding: 2%; }"];
+
+/// One `rofi -dmenu` run. Every menu goes through this, so the switches a
+/// menu uses are spelled out where it is shown; unset fields keep rofi's
+/// defaults.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Dmenu<'a> {
+    pub prompt: &'a str,
+    /// Help line above the list (`-mesg`).
+    pub mesg: Option<&'a str>,
+    /// Text already typed into the input (`-filter`).
+    pub filter: Option<&'a str>,
+    /// Keys bound to `-kb-custom-1`, `-kb-custom-2`, …; pressing one makes
+    /// rofi exit with 10, 11, … instead of 0.
+    pub keys: &'a [&'a str],
+    /// Match entries case-insensitively (`-i`).
+    pub ignore_case: bool,
+    /// Allow picking several entries, returned one per line.
+    pub multi_select: bool,
+    /// What rofi prints for the pick (`-format`), the entry's text by default.
+    pub format: Option<&'a str>,
+    /// Theme and colour switches passed through as they are.
+    pub theme: &'a [&'a str],
+}
+
+impl Dmenu<'_> {
+    /// Shows `menu`, one entry per line, and returns rofi's exit code with
+    /// its trimmed output: the picked entry, or whatever was typed.
+    pub fn run(&self, menu: &str) -> Result<(i32, String)> {
+        let mut cmd = Command::new("rofi");
+        cmd.arg("-dmenu");
+        if self.ignore_case {
+            cmd.arg("-i");
+        }
+        if self.multi_select {
+            cmd.arg("-multi-select");
+        }
+        if let Some(format) = self.format {
+            cmd.args(["-format", format]);
+        }
+        cmd.args(["-p", self.prompt]);
+        if let Some(mesg) = self.mesg {
+            cmd.args(["-mesg", mesg]);
+        }
+        if let Some(filter) = self.filter {
+            cmd.args(["-filter", filter]);
+        }
+        for (n, key) in (1..).zip(self.keys) {
+            cmd.arg(format!("-kb-custom-{}", n)).arg(key);
+        }
+        cmd.args(self.theme);
+
+        let mut child = cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(menu.as_bytes())?;
+            stdin.flush()?;
+        }
+
+        let output = child.wait_with_output()?;
+        Ok((
+            output.status.code().unwrap_or(1),
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ))
+    }
+}
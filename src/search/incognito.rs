@@ -1,6 +1,4 @@
 use anyhow::Result;
-use std::process::Command;
-use urlencoding::encode;
 use super::prompt;
 use crate::config::BrowserConfig;
 
@@ -10,23 +8,11 @@ pub async fn run(config: &BrowserConfig) -> Result<()> {
         return Ok(());
     }
     
-    let search_url = format!(
-        "https://search.brave.com/search?q={}",
-        encode(&query)
-    );
+    let search_url = super::url(&query);
     
-    Command::new("sh")
-        .arg("-c")
-        .arg(format!("{} --incognito '{}' >/dev/null 2>&1 &", config.executable, search_url))
-        .spawn()?;
-    
-    focus_browser(config);
-    
-    Ok(())
-}
+    let watch = crate::focus::Watch::start(config).await;
+    crate::open_incognito(&[search_url], config).await?;
+    watch.focus_next().await;
 
-fn focus_browser(config: &BrowserConfig) {
-    let _ = Command::new("i3-msg")
-        .arg(format!("[class=\"{}\"] focus", config.window_class))
-        .output();
+    Ok(())
 }
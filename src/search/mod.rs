@@ -1,16 +1,76 @@
 pub mod regular;
 pub mod incognito;
 
+use url::Url;
+use urlencoding::encode;
+
+/// Brave Search results for `query`.
+pub fn url(query: &str) -> String {
+    format!("https://search.brave.com/search?q={}", encode(query))
+}
+
+/// What to open for pasted text: the first link in it, a bare host such as
+/// `docs.rs/tokio`, or else a search for the whole text.
+pub fn resolve(text: &str) -> String {
+    let link = text
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| "<>()[]\"'.,;".contains(c)))
+        .find(|word| Url::parse(word).is_ok_and(|u| matches!(u.scheme(), "http" | "https")));
+    if let Some(link) = link {
+        return link.to_string();
+    }
+
+    let text = text.trim();
+    let host = text.split(['/', ':']).next().unwrap_or_default();
+    let local = host == "localhost" || host.parse::<std::net::IpAddr>().is_ok();
+    let looks_like_host = !text.contains(char::is_whitespace)
+        && (local || host.contains('.') && !host.starts_with('.') && !host.ends_with('.'));
+    // Local servers rarely have certificates.
+    let scheme = if local { "http" } else { "https" };
+    match Url::parse(&format!("{}://{}", scheme, text)) {
+        Ok(url) if looks_like_host => url.to_string(),
+        _ => url(text),
+    }
+}
+
 /// Shared helper for prompting search text
 pub fn prompt(query_label: &str) -> String {
-    use std::process::Command;
+    crate::rofi::Dmenu { prompt: query_label, ..Default::default() }
+        .run("")
+        .map(|(_, text)| text)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_open_as_they_are() {
+        assert_eq!(resolve("https://docs.rs/tokio?x=1#y"), "https://docs.rs/tokio?x=1#y");
+        assert_eq!(resolve("  http://example.com/  "), "http://example.com/");
+    }
 
-    let out = Command::new("rofi")
-        .args(["-dmenu", "-p", query_label])
-        .output();
+    #[test]
+    fn the_first_link_in_text_wins() {
+        assert_eq!(resolve("see <https://example.com/a>, or https://example.com/b."), "https://example.com/a");
+        assert_eq!(resolve("(https://example.com/a)"), "https://example.com/a");
+        assert_eq!(resolve("ftp://example.com/a"), url("ftp://example.com/a"));
+    }
+
+    #[test]
+    fn bare_hosts_get_a_scheme() {
+        assert_eq!(resolve("docs.rs/tokio"), "https://docs.rs/tokio");
+        assert_eq!(resolve("example.com"), "https://example.com/");
+        assert_eq!(resolve("localhost:8080/a"), "http://localhost:8080/a");
+        assert_eq!(resolve("127.0.0.1:3000"), "http://127.0.0.1:3000/");
+    }
 
-    match out {
-        Ok(o) => String::from_utf8_lossy(&o.stdout).trim().to_string(),
-        Err(_) => String::new(),
+    #[test]
+    fn single_words_and_phrases_are_searched() {
+        assert_eq!(resolve("rust"), "https://search.brave.com/search?q=rust");
+        assert_eq!(resolve("tokio.rs vs async-std"), "https://search.brave.com/search?q=tokio.rs%20vs%20async-std");
+        assert_eq!(resolve(".hidden"), url(".hidden"));
+        assert_eq!(resolve("v1.2."), url("v1.2."));
     }
 }
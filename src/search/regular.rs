@@ -1,6 +1,5 @@
 use anyhow::Result;
-use urlencoding::encode;
-use crate::{open_tab, focus_browser, config::BrowserConfig};
+use crate::config::BrowserConfig;
 use super::prompt;
 
 pub async fn run(config: &BrowserConfig) -> Result<()> {
@@ -8,11 +7,6 @@ pub async fn run(config: &BrowserConfig) -> Result<()> {
     if query.is_empty() {
         return Ok(());
     }
-    let url = format!(
-        "https://search.brave.com/search?q={}",
-        encode(&query)
-    );
-    open_tab(&url, config).await?;
-    focus_browser(config);
-    Ok(())
+    let url = super::url(&query);
+    crate::switch_or_open(&url, false, config).await
 }
@@ -0,0 +1,212 @@
+use anyhow::Result;
+use serde_json::json;
+use std::time::{SystemTime, UNIX_EPOCH};
+use urlencoding::{decode, encode};
+use crate::config::BrowserConfig;
+use crate::{call_cdp_batch, call_tab_batch, connect_cdp, Tab};
+
+const PREFIX: &str = "data:text/html;charset=utf-8,";
+/// Start of every placeholder page, followed by its JSON `{url, title}`.
+const HEAD: &str = "<!doctype html><!--bbr-suspended-->\n<script>const page = ";
+
+/// The placeholder reloads the page as soon as its tab is shown again.
+const BODY: &str = r#";
+document.title = "💤 " + page.title;
+const restore = () => location.replace(page.url);
+document.addEventListener("visibilitychange", () => { if (!document.hidden) restore(); });
+if (!document.hidden) restore();
+</script>
+<body style="font: 16px sans-serif; margin: 2em">
+<p>Suspended by bbr: <a id="link"></a></p>
+<script>link.href = page.url; link.textContent = page.title;</script>
+"#;
+
+/// A lightweight `data:` page standing in for `url` until it is shown.
+fn placeholder(title: &str, url: &str) -> String {
+    // `\u003c` keeps `</script>` and `<!--` in the title or URL from ending
+    // the script, and is still valid JSON for `original`.
+    let page = json!({ "url": url, "title": title }).to_string().replace('<', "\\u003c");
+    format!("{}{}", PREFIX, encode(&format!("{}{}{}", HEAD, page, BODY)))
+}
+
+/// The title and URL a placeholder stands in for, or `None` for any other page.
+pub fn original(url: &str) -> Option<(String, String)> {
+    let html = decode(url.strip_prefix(PREFIX)?).ok()?;
+    let page = html.strip_prefix(HEAD)?.split(";\n").next()?;
+    let page: serde_json::Value = serde_json::from_str(page).ok()?;
+    Some((page["title"].as_str()?.to_string(), page["url"].as_str()?.to_string()))
+}
+
+/// Whether a tab may be suspended: a web page that isn't already suspended
+/// and isn't playing sound.
+pub fn candidate(tab: &Tab) -> bool {
+    !tab.suspended && tab.audio.is_none() && (tab.url.starts_with("http://") || tab.url.starts_with("https://"))
+}
+
+/// Replaces each background tab with a placeholder, freeing its renderer;
+/// tabs that are visible in their window are left alone. Returns how many
+/// were suspended.
+pub async fn suspend(tabs: &[&Tab], config: &BrowserConfig) -> Result<usize> {
+    let mut ws = connect_cdp(config).await?;
+    let attach = tabs
+        .iter()
+        .map(|t| ("Target.attachToTarget", json!({ "targetId": t.target_id, "flatten": true })))
+        .collect();
+    let replies = call_cdp_batch(&mut ws, 1, attach).await?;
+    let attached: Vec<(&Tab, &str)> = tabs
+        .iter()
+        .zip(&replies)
+        .filter_map(|(t, r)| Some((*t, r["sessionId"].as_str()?)))
+        .collect();
+
+    let hidden = attached
+        .iter()
+        .map(|(_, session)| (*session, "Runtime.evaluate", json!({ "expression": "document.hidden", "returnByValue": true })))
+        .collect();
+    let hidden = call_tab_batch(&mut ws, 1000, hidden).await?;
+
+    let navigate: Vec<(&str, &str, serde_json::Value)> = attached
+        .iter()
+        .zip(&hidden)
+        .filter(|(_, h)| h["result"]["value"] == true)
+        .map(|((tab, session), _)| (*session, "Page.navigate", json!({ "url": placeholder(&tab.title, &tab.url) })))
+        .collect();
+    let count = navigate.len();
+    call_tab_batch(&mut ws, 2000, navigate).await?;
+    Ok(count)
+}
+
+/// `bbr tabs suspend [--idle <secs>]`: suspends background tabs, only those
+/// the session file says were last active more than `idle` seconds ago.
+pub async fn suspend_idle(idle: Option<i64>, config: &BrowserConfig) -> Result<()> {
+    let mut tabs = crate::get_tabs(config).await?;
+    // Without this every tab looks silent, and playing ones would be suspended.
+    crate::audio::assign(&mut tabs, config).await;
+    let chosen: Vec<&Tab> = match idle {
+        None => tabs.iter().filter(|t| candidate(t)).collect(),
+        Some(idle) => {
+            let cutoff = now() - idle;
+            let last_active = crate::groups::last_active(&tabs, config)?;
+            tabs.iter()
+                .zip(last_active)
+                .filter(|(t, active)| candidate(t) && active.is_some_and(|a| a < cutoff))
+                .map(|(t, _)| t)
+                .collect()
+        }
+    };
+    let count = suspend(&chosen, config).await?;
+    println!("Suspended {} tab{}", count, if count == 1 { "" } else { "s" });
+    Ok(())
+}
+
+/// Pick background tabs to suspend, longest idle first.
+pub async fn suspend_menu(tabs: &[Tab], config: &BrowserConfig) -> Result<()> {
+    let last_active = crate::groups::last_active(tabs, config).unwrap_or_else(|_| vec![None; tabs.len()]);
+    let mut candidates: Vec<(&Tab, Option<i64>)> = tabs
+        .iter()
+        .zip(last_active)
+        .filter(|(t, _)| candidate(t))
+        .collect();
+    candidates.sort_by_key(|(_, active)| active.unwrap_or(i64::MAX));
+
+    let now = now();
+    let lines: Vec<String> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, (t, active))| {
+            let idle = active.map(|a| format!("idle {}", ago(now - a))).unwrap_or_default();
+            format!("{}. {:<9} {} - {}", i + 1, idle, t.title, t.url)
+        })
+        .collect();
+    let chosen = crate::rofi_multi_select("Suspend tabs", &lines.join("\n"));
+    let chosen: Vec<&Tab> = chosen
+        .lines()
+        .filter_map(|line| lines.iter().position(|l| l == line))
+        .map(|i| candidates[i].0)
+        .collect();
+    if !chosen.is_empty() {
+        suspend(&chosen, config).await?;
+    }
+    Ok(())
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+/// `45m`, `3h` or `2d`.
+fn ago(secs: i64) -> String {
+    match secs {
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86_400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::Audio;
+
+    fn tab(url: &str) -> Tab {
+        Tab {
+            target_id: "A".to_string(),
+            title: "Title".to_string(),
+            url: url.to_string(),
+            incognito: false,
+            window_id: None,
+            group: None,
+            audio: None,
+            usage: None,
+            suspended: false,
+        }
+    }
+
+    #[test]
+    fn background_web_pages_are_candidates() {
+        assert!(candidate(&tab("https://example.com/")));
+        assert!(candidate(&tab("http://example.com/")));
+    }
+
+    #[test]
+    fn tabs_playing_sound_are_not_candidates() {
+        let mut playing = tab("https://example.com/");
+        playing.audio = Some(Audio::Playing);
+        assert!(!candidate(&playing));
+
+        let mut muted = tab("https://example.com/");
+        muted.audio = Some(Audio::Muted);
+        assert!(!candidate(&muted));
+    }
+
+    #[test]
+    fn suspended_and_internal_pages_are_not_candidates() {
+        let mut suspended = tab("https://example.com/");
+        suspended.suspended = true;
+        assert!(!candidate(&suspended));
+        assert!(!candidate(&tab("brave://settings/")));
+        assert!(!candidate(&tab("file:///tmp/a.html")));
+    }
+
+    #[test]
+    fn placeholders_give_back_their_page() {
+        let title = r#"<b>"Quotes"</b> & </script><!-- more"#;
+        let url = r#"https://example.com/?q=</script>&t="x"<y>#top"#;
+        let page = placeholder(title, url);
+        assert!(page.starts_with(PREFIX));
+        assert_eq!(original(&page), Some((title.to_string(), url.to_string())));
+    }
+
+    #[test]
+    fn placeholders_keep_script_tags_out_of_the_page() {
+        let page = placeholder("</script>", "https://example.com/</script>");
+        let html = decode(page.strip_prefix(PREFIX).unwrap()).unwrap();
+        assert_eq!(html.matches("</script>").count(), BODY.matches("</script>").count());
+    }
+
+    #[test]
+    fn other_pages_have_no_original() {
+        assert_eq!(original("https://example.com/"), None);
+        assert_eq!(original("data:text/html;charset=utf-8,%3Cp%3Ehi"), None);
+    }
+}
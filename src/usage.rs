@@ -0,0 +1,129 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::time::Duration;
+use crate::config::BrowserConfig;
+use crate::{call_cdp, call_cdp_batch, call_tab_batch, connect_cdp, CdpSocket, Tab};
+
+/// CPU use is the task time a page spent between two samples this far apart.
+const SAMPLE: Duration = Duration::from_millis(300);
+/// Tabs that don't answer in time (frozen or still loading) show no usage.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Usage {
+    /// Bytes of JavaScript heap in use.
+    pub heap: u64,
+    /// Percent of one core over the sample.
+    pub cpu: f64,
+}
+
+impl Usage {
+    /// Fixed-width `  123 MB   4%` column for menus.
+    pub fn column(self) -> String {
+        format!("{:>5} MB {:>3.0}%", self.heap / 1_000_000, self.cpu)
+    }
+}
+
+/// Renderer processes and their combined CPU use, from
+/// `SystemInfo.getProcessInfo`; CDP doesn't say which process runs which tab.
+pub struct Renderers {
+    pub count: usize,
+    pub cpu: f64,
+}
+
+/// Sets `tab.usage` from two `Performance.getMetrics` samples per tab.
+pub async fn assign(ws: &mut CdpSocket, tabs: &mut [Tab]) -> Option<Renderers> {
+    let (usage, renderers) = tokio::time::timeout(TIMEOUT, sample(ws, tabs)).await.ok()?.ok()?;
+    for (tab, usage) in tabs.iter_mut().zip(usage) {
+        tab.usage = usage;
+    }
+    renderers
+}
+
+async fn sample(ws: &mut CdpSocket, tabs: &[Tab]) -> Result<(Vec<Option<Usage>>, Option<Renderers>)> {
+    let attach = tabs
+        .iter()
+        .map(|t| ("Target.attachToTarget", json!({ "targetId": t.target_id, "flatten": true })))
+        .collect();
+    let replies = call_cdp_batch(ws, 400, attach).await?;
+    let sessions: Vec<Option<&str>> = replies.iter().map(|r| r["sessionId"].as_str()).collect();
+    let attached: Vec<&str> = sessions.iter().flatten().copied().collect();
+
+    let enable = attached.iter().map(|s| (*s, "Performance.enable", json!({}))).collect();
+    call_tab_batch(ws, 500, enable).await?;
+
+    let metrics = || attached.iter().map(|s| (*s, "Performance.getMetrics", json!({}))).collect();
+    let before = call_tab_batch(ws, 600, metrics()).await?;
+    let processes_before = call_cdp(ws, 700, "SystemInfo.getProcessInfo", json!({})).await.ok();
+    tokio::time::sleep(SAMPLE).await;
+    let processes_after = call_cdp(ws, 701, "SystemInfo.getProcessInfo", json!({})).await.ok();
+    let after = call_tab_batch(ws, 800, metrics()).await?;
+
+    let mut samples = before.iter().zip(&after).map(|(b, a)| usage(b, a));
+    let usage = sessions.iter().map(|s| s.and_then(|_| samples.next().flatten())).collect();
+    let renderers = processes_before.zip(processes_after).map(|(b, a)| renderers(&b, &a));
+    Ok((usage, renderers))
+}
+
+fn usage(before: &Value, after: &Value) -> Option<Usage> {
+    let heap = metric(after, "JSHeapUsedSize")?;
+    let wall = metric(after, "Timestamp")? - metric(before, "Timestamp")?;
+    let busy = metric(after, "TaskDuration")? - metric(before, "TaskDuration")?;
+    Some(Usage {
+        heap: heap as u64,
+        cpu: if wall > 0.0 { busy / wall * 100.0 } else { 0.0 },
+    })
+}
+
+fn metric(reply: &Value, name: &str) -> Option<f64> {
+    reply["metrics"].as_array()?.iter().find(|m| m["name"] == name)?["value"].as_f64()
+}
+
+fn renderers(before: &Value, after: &Value) -> Renderers {
+    let cpu_times = |reply: &Value| -> Vec<(i64, f64)> {
+        reply["processInfo"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|p| p["type"] == "renderer")
+            .filter_map(|p| Some((p["id"].as_i64()?, p["cpuTime"].as_f64()?)))
+            .collect()
+    };
+    let before = cpu_times(before);
+    let after = cpu_times(after);
+    let busy: f64 = after
+        .iter()
+        .filter_map(|(id, t)| Some(t - before.iter().find(|(b, _)| b == id)?.1))
+        .sum();
+    Renderers { count: after.len(), cpu: busy / SAMPLE.as_secs_f64() * 100.0 }
+}
+
+/// Lists tabs by memory, heaviest first, and closes the ones picked.
+pub async fn close_heaviest(tabs: &[Tab], config: &BrowserConfig) -> Result<()> {
+    let mut tabs = tabs.to_vec();
+    let mut ws = connect_cdp(config).await?;
+    let renderers = assign(&mut ws, &mut tabs).await;
+    drop(ws);
+    tabs.sort_by_key(|t| std::cmp::Reverse(t.usage.map(|u| u.heap)));
+
+    let lines: Vec<String> = tabs
+        .iter()
+        .enumerate()
+        .map(|(i, t)| {
+            let column = t.usage.map(Usage::column).unwrap_or_else(|| format!("{:>12}", "?"));
+            format!("{}. {}  {} - {}", i + 1, column, t.title, t.url)
+        })
+        .collect();
+    let prompt = match renderers {
+        Some(r) => format!("Close tabs ({} renderers, {:.0}% CPU)", r.count, r.cpu),
+        None => "Close tabs".to_string(),
+    };
+
+    let chosen = crate::rofi_multi_select(&prompt, &lines.join("\n"));
+    for line in chosen.lines() {
+        if let Some(tab) = lines.iter().position(|l| l == line).map(|i| &tabs[i]) {
+            let _ = crate::close_tab(&tab.target_id, config).await;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use crate::config::BrowserConfig;
+use crate::{call_cdp, connect_cdp, CdpSocket, Tab};
+
+/// Menu label and `Browser.setWindowBounds` window state.
+const STATES: [(&str, &str); 4] = [
+    ("Minimize", "minimized"),
+    ("Maximize", "maximized"),
+    ("Fullscreen", "fullscreen"),
+    ("Restore", "normal"),
+];
+
+/// Looks up each tab's window and orders tabs window by window, windows in
+/// the order their first tab appears, incognito tabs still last.
+pub async fn assign(ws: &mut CdpSocket, tabs: &mut [Tab]) -> Result<()> {
+    let calls = tabs
+        .iter()
+        .map(|t| ("Browser.getWindowForTarget", json!({ "targetId": t.target_id })))
+        .collect();
+    let replies = crate::call_cdp_batch(ws, 100, calls).await?;
+    for (tab, reply) in tabs.iter_mut().zip(replies) {
+        tab.window_id = reply["windowId"].as_i64();
+    }
+
+    let order = window_order(tabs);
+    tabs.sort_by_key(|t| (t.incognito, order.iter().position(|w| Some(*w) == t.window_id)));
+    Ok(())
+}
+
+/// Window ids in the order their first tab appears.
+pub fn window_order(tabs: &[Tab]) -> Vec<i64> {
+    let mut order = Vec::new();
+    for id in tabs.iter().filter_map(|t| t.window_id) {
+        if !order.contains(&id) {
+            order.push(id);
+        }
+    }
+    order
+}
+
+/// Main menu heading for window number `n`, counting from 1.
+pub fn heading(n: usize, tabs: usize) -> String {
+    format!("Window {}: {} tab{}", n, tabs, if tabs == 1 { "" } else { "s" })
+}
+
+/// The window a selected heading stands for.
+pub fn parse_heading(sel: &str, tabs: &[Tab]) -> Option<i64> {
+    let n: usize = sel.strip_prefix("Window ")?.split(':').next()?.parse().ok()?;
+    window_order(tabs).get(n.checked_sub(1)?).copied()
+}
+
+/// Focus, minimize, maximize, fullscreen or restore one window.
+pub async fn window_menu(window: i64, tabs: &[Tab], config: &BrowserConfig) -> Result<()> {
+    let mut options = vec!["Focus"];
+    options.extend(STATES.iter().map(|(label, _)| *label));
+    let choice = crate::rofi_choose("Window", &options);
+
+    if choice == "Focus" {
+        let tab = tabs
+            .iter()
+            .find(|t| t.window_id == Some(window))
+            .ok_or_else(|| anyhow!("Window {} has no tabs", window))?;
+        crate::focus::tab(&tab.target_id, &tab.title, config).await?;
+    } else if let Some((_, state)) = STATES.iter().find(|(label, _)| *label == choice) {
+        set_state(window, state, config).await?;
+    }
+    Ok(())
+}
+
+pub async fn set_state(window: i64, state: &str, config: &BrowserConfig) -> Result<()> {
+    let mut ws = connect_cdp(config).await?;
+    call_cdp(
+        &mut ws,
+        1,
+        "Browser.setWindowBounds",
+        json!({ "windowId": window, "bounds": { "windowState": state } }),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Every window `tab` could move to, as `(label, window id)`, plus a new one.
+pub fn destinations(tab: &Tab, tabs: &[Tab]) -> Vec<(String, Option<i64>)> {
+    let mut destinations: Vec<(String, Option<i64>)> = window_order(tabs)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, id)| Some(*id) != tab.window_id)
+        // Incognito and regular tabs can't share a window.
+        .filter(|(_, id)| tabs.iter().any(|t| t.window_id == Some(*id) && t.incognito == tab.incognito))
+        .map(|(i, id)| {
+            let count = tabs.iter().filter(|t| t.window_id == Some(id)).count();
+            (heading(i + 1, count), Some(id))
+        })
+        .collect();
+    destinations.push(("new window".to_string(), None));
+    destinations
+}
+
+/// CDP can't reattach a tab to another window, so the page is reopened there
+/// and the original closed; its back/forward history stays behind.
+pub async fn move_tab(tab: &Tab, to: Option<i64>, tabs: &[Tab], config: &BrowserConfig) -> Result<()> {
+    let mut ws = connect_cdp(config).await?;
+    let mut params = json!({ "url": tab.url });
+    if tab.incognito {
+        let info = call_cdp(&mut ws, 1, "Target.getTargetInfo", json!({ "targetId": tab.target_id })).await?;
+        params["browserContextId"] = info["targetInfo"]["browserContextId"].clone();
+    }
+
+    match to {
+        None => params["newWindow"] = json!(true),
+        Some(window) => {
+            // New tabs open in the last focused window, so focus one of its tabs first.
+            let anchor = tabs
+                .iter()
+                .find(|t| t.window_id == Some(window))
+                .ok_or_else(|| anyhow!("Window {} has no tabs", window))?;
+            call_cdp(&mut ws, 2, "Target.activateTarget", json!({ "targetId": anchor.target_id })).await?;
+        }
+    }
+
+    call_cdp(&mut ws, 3, "Target.createTarget", params).await?;
+    call_cdp(&mut ws, 4, "Target.closeTarget", json!({ "targetId": tab.target_id })).await?;
+    Ok(())
+}